@@ -1,16 +1,50 @@
 use crate::config::FileConfig;
+use crate::log_warn;
+use crate::logger::{ColorChoice, LogFormat};
+use crate::output::Formatter;
 use anyhow::{Result, anyhow};
-use clap::{ArgAction, Parser, builder::ValueHint};
-use std::path::PathBuf;
+use clap::{
+    ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum, builder::ValueHint,
+    parser::ValueSource,
+};
+use clap_complete::Shell;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 
 const BUTTON_ROW_BREAK: &str = "__ROW_BREAK__";
+const STDIN_SENTINEL: &str = "-";
+const LOCAL_API_URL: &str = "http://localhost:8081/bot";
+const DICE_EMOJIS: [&str; 6] = ["🎲", "🎯", "🏀", "⚽", "🎳", "🎰"];
 
 #[derive(Debug, Clone)]
 pub enum ButtonSpec {
     Link { text: String, url: String },
+    Callback { text: String, data: String },
     RowBreak,
 }
 
+#[derive(Debug, Clone)]
+pub struct ReplyKeyboard {
+    pub rows: Vec<Vec<String>>,
+    pub one_time: bool,
+    pub resize: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContactSpec {
+    pub first_name: String,
+    pub last_name: Option<String>,
+    pub phone: String,
+    pub vcard: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SortBy {
+    Name,
+    Size,
+    Mtime,
+}
+
 #[derive(Parser, Debug)]
 #[command(
     name = "sendtg:",
@@ -22,12 +56,114 @@ struct Cli {
     setup: bool,
     #[arg(long = "show-config", help = "Print current config contents and exit.")]
     show_config: bool,
+    #[arg(
+        long = "validate-config",
+        help = "Check the config file and verify the bot token with getMe, then exit."
+    )]
+    validate_config: bool,
+    #[arg(
+        long = "reset-config",
+        help = "Back up the config file to config.toml.bak and start from an empty one."
+    )]
+    reset_config: bool,
+    #[arg(
+        long = "import-config",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        help = "Replace the config with settings from a JSON or TOML file (the old one is backed up)."
+    )]
+    import_config: Option<PathBuf>,
+    #[arg(
+        long = "import-format",
+        value_name = "FORMAT",
+        requires = "import_config",
+        help = "Format of the --import-config file (default: from its extension)."
+    )]
+    import_format: Option<crate::config::ConfigFormat>,
+    #[arg(
+        long = "set",
+        num_args = 2,
+        value_names = ["FIELD", "VALUE"],
+        allow_hyphen_values = true,
+        help = "Set one config field (api_url, bot_token or chat_id) and exit."
+    )]
+    set: Option<Vec<String>>,
+    #[arg(
+        long = "unset",
+        value_name = "FIELD",
+        conflicts_with = "set",
+        help = "Clear one config field (api_url, bot_token or chat_id) and exit."
+    )]
+    unset: Option<String>,
+    #[arg(
+        short = 'y',
+        long = "yes",
+        requires = "reset_config",
+        help = "Do not ask for confirmation before resetting the config."
+    )]
+    yes: bool,
+    #[arg(
+        long = "export",
+        requires = "show_config",
+        help = "With --show-config, print `export SENDTG_*=...` lines for eval in a shell."
+    )]
+    export: bool,
+    #[arg(
+        long = "redact",
+        requires = "export",
+        help = "Redact the bot token in --export output."
+    )]
+    redact: bool,
+    #[arg(
+        long = "completions",
+        value_name = "SHELL",
+        value_parser = clap::value_parser!(Shell),
+        help = "Print a shell completion script (bash, zsh, fish, powershell, elvish) and exit."
+    )]
+    completions: Option<Shell>,
+    #[arg(long = "man-page", help = "Print a man page in roff format and exit.")]
+    man_page: bool,
     #[arg(
         short = 'a',
         long = "api_url",
         help = "Override the Telegram API base URL."
     )]
     api_url: Option<String>,
+    #[arg(
+        long = "config-max-age",
+        value_name = "DAYS",
+        help = "Warn when the config file is older than DAYS (default 90, 0 to disable)."
+    )]
+    config_max_age: Option<u64>,
+    #[arg(
+        long = "audit-log",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        help = "Append a JSON line per send to PATH (default: audit.log next to the config file)."
+    )]
+    audit_log: Option<PathBuf>,
+    #[arg(
+        long = "no-audit",
+        conflicts_with = "audit_log",
+        help = "Do not write the audit log."
+    )]
+    no_audit: bool,
+    #[arg(
+        long = "test-api",
+        help = "Use Telegram's test environment (needs a bot created on the test server)."
+    )]
+    test_api: bool,
+    #[arg(
+        long = "no-verify-ssl",
+        help = "Skip TLS certificate checks (self-signed local Bot API servers only)."
+    )]
+    no_verify_ssl: bool,
+    #[arg(
+        long = "local-api",
+        conflicts_with = "api_url",
+        help = "Use a local Bot API server at http://localhost:8081 (no 50 MB upload limit)."
+    )]
+    local_api: bool,
     #[arg(short = 't', long = "bot_token", help = "Override the bot token.")]
     bot_token: Option<String>,
     #[arg(
@@ -37,6 +173,51 @@ struct Cli {
         allow_hyphen_values = true
     )]
     chat_id: Option<String>,
+    #[arg(
+        long = "batch-csv",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        conflicts_with = "broadcast",
+        help = "Send one message per row of a CSV file with chat_id, message, media_path and caption columns."
+    )]
+    batch_csv: Option<PathBuf>,
+    #[arg(
+        long = "batch-json",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        conflicts_with_all = ["broadcast", "batch_csv"],
+        help = "Send each object of a JSON array (chat_id, message, media, caption, silent)."
+    )]
+    batch_json: Option<PathBuf>,
+    #[arg(
+        long = "queue",
+        value_name = "FILE",
+        value_hint = ValueHint::FilePath,
+        conflicts_with_all = ["broadcast", "batch_csv", "batch_json", "flush_queue", "dry_run"],
+        help = "Append this send (chat, message, media, caption) to a queue file instead of sending it."
+    )]
+    queue: Option<PathBuf>,
+    #[arg(
+        long = "flush-queue",
+        value_name = "FILE",
+        value_hint = ValueHint::FilePath,
+        conflicts_with_all = ["broadcast", "batch_csv", "batch_json"],
+        help = "Send every entry of a queue file in order, removing each one once it is sent."
+    )]
+    flush_queue: Option<PathBuf>,
+    #[arg(
+        long = "broadcast",
+        conflicts_with_all = ["chat_id", "chat_username"],
+        help = "Send to every [profiles.<name>] target in the config file."
+    )]
+    broadcast: bool,
+    #[arg(
+        long = "chat-username",
+        value_name = "@NAME",
+        conflicts_with = "chat_id",
+        help = "Target a public group or channel by its @username."
+    )]
+    chat_username: Option<String>,
     #[arg(
         short = 'm',
         long = "media",
@@ -46,8 +227,68 @@ struct Cli {
         help = "Attach files to send as media."
     )]
     media: Vec<PathBuf>,
+    #[arg(
+        long = "media-file",
+        alias = "files-from",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        help = "Read media paths from a file, one per line ('#' starts a comment)."
+    )]
+    media_file: Option<PathBuf>,
+    #[arg(
+        long = "send-album-from-dir",
+        value_name = "DIR",
+        value_hint = ValueHint::DirPath,
+        help = "Send the photos, videos and audio files in a directory."
+    )]
+    album_dir: Option<PathBuf>,
+    #[arg(
+        long = "recursive",
+        requires = "album_dir",
+        help = "Include subdirectories of --send-album-from-dir."
+    )]
+    recursive: bool,
+    #[arg(
+        long = "sort-by",
+        value_enum,
+        help = "Sort media by name, size or modification time before sending."
+    )]
+    sort_by: Option<SortBy>,
+    #[arg(long = "reverse", help = "Reverse the media order (after --sort-by).")]
+    reverse: bool,
+    #[arg(
+        long = "exclude",
+        value_name = "PATTERN",
+        action = ArgAction::Append,
+        help = "Skip directory or glob matches whose file name matches this pattern; repeatable."
+    )]
+    exclude: Vec<String>,
+    #[arg(
+        long = "since",
+        value_name = "DATE",
+        help = "Only send media modified on or after this date (YYYY-MM-DD, local time)."
+    )]
+    since: Option<String>,
+    #[arg(
+        long = "no-cache",
+        help = "Always upload files instead of reusing cached Telegram file_ids."
+    )]
+    no_cache: bool,
+    #[arg(
+        long = "check-files",
+        help = "Check that every media file exists and is readable before any API call."
+    )]
+    check_files: bool,
     #[arg(long = "spoiler", help = "Flag media as spoiler.")]
     spoiler: bool,
+    #[arg(
+        long = "spoiler-nth",
+        value_name = "N",
+        action = ArgAction::Append,
+        conflicts_with = "spoiler",
+        help = "Flag only the Nth media item (1-based) as spoiler; repeat for several items."
+    )]
+    spoiler_nth: Vec<usize>,
     #[arg(
         long = "streaming",
         help = "Set supports_streaming on videos so they can be played while downloading."
@@ -59,12 +300,112 @@ struct Cli {
         help = "Delay (seconds) between media requests to reduce rate limiting."
     )]
     delay_secs: Option<u64>,
+    #[arg(
+        long = "thumbnail",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        help = "Use this JPEG/PNG as the thumbnail instead of generating one with ffmpeg."
+    )]
+    thumbnail: Option<PathBuf>,
+    #[arg(
+        long = "thumbnail-at",
+        value_name = "SECONDS",
+        conflicts_with_all = ["thumbnail", "skip_thumbnail"],
+        help = "Grab the video thumbnail at this timestamp instead of a random frame."
+    )]
+    thumbnail_at: Option<f64>,
+    #[arg(
+        long = "thumbnail-size",
+        value_name = "WxH",
+        conflicts_with_all = ["thumbnail", "skip_thumbnail"],
+        help = "Maximum size of generated thumbnails (default 320x320)."
+    )]
+    thumbnail_size: Option<String>,
+    #[arg(
+        long = "skip-thumbnail",
+        alias = "no-thumbnail",
+        conflicts_with = "thumbnail",
+        help = "Skip all ffmpeg/ffprobe metadata and thumbnail extraction."
+    )]
+    skip_thumbnail: bool,
+    #[arg(
+        long = "strip-exif",
+        help = "Remove EXIF and other metadata (GPS, device info) from images before upload."
+    )]
+    strip_exif: bool,
+    #[arg(
+        long = "ffmpeg-path",
+        value_name = "PATH",
+        value_hint = ValueHint::ExecutablePath,
+        help = "ffmpeg binary to use (default: ffmpeg on PATH or ffmpeg_path from the config)."
+    )]
+    ffmpeg_path: Option<String>,
+    #[arg(
+        long = "ffprobe-path",
+        value_name = "PATH",
+        value_hint = ValueHint::ExecutablePath,
+        help = "ffprobe binary to use (default: ffprobe on PATH or ffprobe_path from the config)."
+    )]
+    ffprobe_path: Option<String>,
+    #[arg(
+        long = "metadata-timeout",
+        value_name = "SECONDS",
+        help = "Kill ffmpeg/ffprobe if metadata, thumbnail or conversion work takes longer (default 30)."
+    )]
+    metadata_timeout: Option<u64>,
+    #[arg(
+        long = "max-file-size",
+        value_name = "BYTES",
+        help = "Refuse to upload media larger than this (bytes, or with a K/M/G suffix)."
+    )]
+    max_file_size: Option<String>,
+    #[arg(
+        long = "large-file",
+        help = "Stream uploads in fixed-size chunks with a known length (for local Bot API servers)."
+    )]
+    large_file: bool,
+    #[arg(
+        long = "chunk-size",
+        value_name = "BYTES",
+        help = "Read size for --large-file uploads (default 8M; K/M/G suffixes allowed)."
+    )]
+    chunk_size: Option<String>,
+    #[arg(
+        long = "video-note",
+        help = "Send each video as a round video note (no captions or buttons)."
+    )]
+    video_note: bool,
+    #[arg(
+        long = "voice",
+        conflicts_with = "video_note",
+        help = "Send audio as voice messages, converting to OGG Opus with ffmpeg if needed."
+    )]
+    voice: bool,
     #[arg(
         long = "no-group",
         alias = "no_group",
         help = "Send media one by one instead of an album."
     )]
     no_group: bool,
+    #[arg(
+        long = "no-group-documents",
+        help = "Send documents one by one but keep photos and videos in albums."
+    )]
+    no_group_documents: bool,
+    #[arg(
+        long = "no-group-photos",
+        help = "Send photos and videos one by one but keep documents in albums."
+    )]
+    no_group_photos: bool,
+    #[arg(
+        long = "parallel",
+        value_name = "N",
+        requires = "no_group",
+        conflicts_with = "delay_secs",
+        value_parser = clap::value_parser!(u16).range(1..=32),
+        help = "With --no-group, upload up to N files at the same time."
+    )]
+    parallel: Option<u16>,
     #[arg(
         short = 'F',
         long = "as-file",
@@ -72,8 +413,49 @@ struct Cli {
         help = "Send media as documents."
     )]
     as_file: bool,
-    #[arg(short = 'C', long = "caption", help = "Caption to reuse across media.")]
-    caption: Option<String>,
+    #[arg(
+        long = "disable-content-type-detection",
+        help = "Stop Telegram from re-typing documents based on their content."
+    )]
+    disable_content_type_detection: bool,
+    #[arg(
+        short = 'C',
+        long = "caption",
+        action = ArgAction::Append,
+        help = "Media caption; repeat to caption each item in order."
+    )]
+    captions: Vec<String>,
+    #[arg(
+        long = "caption-file",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        conflicts_with = "captions",
+        help = "Read the media caption from a file."
+    )]
+    caption_file: Option<PathBuf>,
+    #[arg(
+        long = "caption-parse-mode",
+        value_name = "MODE",
+        value_parser = ["HTML", "MarkdownV2", "Markdown"],
+        help = "Parse mode for media captions (captions are plain text by default)."
+    )]
+    caption_parse_mode: Option<String>,
+    #[arg(
+        long = "prepend-filename",
+        help = "Start each media caption with the file name in bold (uses HTML captions)."
+    )]
+    prepend_filename: bool,
+    #[arg(
+        long = "append-stats",
+        help = "End each media caption with the file size and MIME type (uses HTML captions)."
+    )]
+    append_stats: bool,
+    #[arg(
+        long = "no-trim",
+        requires = "caption_file",
+        help = "Keep trailing whitespace from --caption-file."
+    )]
+    no_trim: bool,
     #[arg(
         long = "button",
         alias = "button-row-break",
@@ -84,6 +466,52 @@ struct Cli {
         help = "Add inline button as 'Label|URL'. Use --button-row-break between buttons to start a new row."
     )]
     buttons: Vec<String>,
+    #[arg(
+        long = "callback",
+        value_name = "LABEL|DATA",
+        action = ArgAction::Append,
+        help = "Add an inline button that sends callback data to the bot as 'Label|data'."
+    )]
+    callbacks: Vec<String>,
+    #[arg(
+        long = "force-reply",
+        conflicts_with_all = ["buttons", "callbacks"],
+        help = "Ask the recipient's client to open a reply to the message."
+    )]
+    force_reply: bool,
+    #[arg(
+        long = "reply-keyboard",
+        value_name = "A|B||C|D",
+        conflicts_with_all = ["buttons", "callbacks", "force_reply"],
+        help = "Show a reply keyboard; '|' separates buttons and '||' separates rows."
+    )]
+    reply_keyboard: Option<String>,
+    #[arg(
+        long = "reply-keyboard-remove",
+        conflicts_with_all = ["buttons", "callbacks", "force_reply", "reply_keyboard"],
+        help = "Remove a reply keyboard previously shown to the recipient."
+    )]
+    reply_keyboard_remove: bool,
+    #[arg(
+        long = "one-time-keyboard",
+        requires = "reply_keyboard",
+        help = "Hide the reply keyboard after one use."
+    )]
+    one_time_keyboard: bool,
+    #[arg(
+        long = "resize-keyboard",
+        requires = "reply_keyboard",
+        help = "Let clients shrink the reply keyboard to fit its buttons."
+    )]
+    resize_keyboard: bool,
+    #[arg(
+        long = "markup-file",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        conflicts_with_all = ["buttons", "callbacks", "force_reply", "reply_keyboard", "reply_keyboard_remove"],
+        help = "Read the reply_markup JSON (any keyboard type) from a file."
+    )]
+    markup_file: Option<PathBuf>,
     #[arg(
         long = "button-text",
         alias = "button_text",
@@ -102,6 +530,21 @@ struct Cli {
     silent: bool,
     #[arg(long = "check", help = "Check connectivity and credentials only.")]
     check: bool,
+    #[arg(
+        long = "get-chat",
+        help = "Print information about the target chat and exit without sending anything."
+    )]
+    get_chat: bool,
+    #[arg(
+        long = "export-invite-link",
+        help = "Create a new invite link for the target chat and print it (bot must be an admin)."
+    )]
+    export_invite_link: bool,
+    #[arg(
+        long = "check-token",
+        help = "Verify the bot token with getMe and print the bot's username (no chat ID needed)."
+    )]
+    check_token: bool,
     #[arg(
         long = "thread-id",
         alias = "thread_id",
@@ -109,157 +552,1920 @@ struct Cli {
         allow_hyphen_values = true
     )]
     thread_id: Option<i64>,
-    #[arg(help = "Message text when no media is provided.")]
-    message: Option<String>,
-}
-
-#[derive(Debug, Clone)]
-pub struct Args {
-    pub api_url: String,
-    pub bot_token: String,
-    pub chat_id: String,
-    pub media_paths: Vec<PathBuf>,
-    pub spoiler: bool,
-    pub streaming: bool,
-    pub delay_secs: Option<u64>,
-    pub no_group: bool,
-    pub as_file: bool,
-    pub caption: Option<String>,
-    pub buttons: Vec<ButtonSpec>,
-    pub message: Option<String>,
-    pub check: bool,
-    pub silent: bool,
-    pub thread_id: Option<i64>,
-    pub provided_api_url: bool,
-    pub provided_bot_token: bool,
-    pub provided_chat_id: bool,
-}
-
-#[derive(Debug, Clone)]
-pub struct SetupArgs {
-    pub api_url: Option<String>,
-    pub bot_token: Option<String>,
-    pub chat_id: Option<String>,
-}
-
-#[derive(Debug, Clone)]
-pub enum ParsedArgs {
-    Run(Args),
-    Setup(SetupArgs),
-    ShowConfig,
-}
-
-impl Args {
-    pub fn parse() -> Result<ParsedArgs> {
-        let cli = Cli::parse();
-
-        if cli.setup {
-            return Ok(ParsedArgs::Setup(SetupArgs {
-                api_url: cli.api_url.clone(),
-                bot_token: cli.bot_token.clone(),
-                chat_id: cli.chat_id.clone(),
-            }));
-        }
-
-        if cli.show_config {
-            return Ok(ParsedArgs::ShowConfig);
-        }
-
-        let file_config = crate::config::load_config()?;
-        let path = crate::config::config_file_path()?;
-
-        let file_config: FileConfig = match file_config {
-            Some(cfg) => cfg,
-            None => {
-                return Err(anyhow!(
-                    "Configuration not found at {}. Run `sendtg --setup` first.",
-                    path.display()
-                ));
-            }
-        };
-
-        if !file_config.has_required_fields() {
-            return Err(anyhow!(
-                "Configuration at {} is missing required fields. Run `sendtg --setup` to populate it.",
-                path.display()
-            ));
-        }
-
-        let api_url = cli
-            .api_url
-            .clone()
-            .or_else(|| file_config.api_url.clone())
-            .ok_or_else(|| anyhow!("API URL is missing from configuration"))?;
-        let bot_token = cli
-            .bot_token
-            .clone()
-            .or_else(|| file_config.bot_token.clone())
-            .ok_or_else(|| anyhow!("Bot token is missing from configuration"))?;
-        let chat_id = cli
-            .chat_id
-            .clone()
-            .or_else(|| file_config.chat_id.clone())
-            .ok_or_else(|| anyhow!("Chat ID is missing from configuration"))?;
-
-        let mut buttons = parse_button_specs(&cli.buttons)?;
-
-        match (&cli.button_text, &cli.button_url) {
-            (Some(text), Some(url)) => buttons.push(ButtonSpec::Link {
-                text: text.clone(),
-                url: url.clone(),
-            }),
-            (Some(_), None) | (None, Some(_)) => {
-                return Err(anyhow!(
-                    "Both --button-text and --button-url must be provided together, or use --button \"Label|URL\"."
-                ));
-            }
-            (None, None) => {}
-        }
-
-        Ok(ParsedArgs::Run(Args {
-            api_url,
-            bot_token,
-            chat_id,
-            media_paths: cli.media.clone(),
-            spoiler: cli.spoiler,
-            streaming: cli.streaming,
-            delay_secs: cli.delay_secs,
-            no_group: cli.no_group,
-            as_file: cli.as_file,
-            caption: cli.caption.clone(),
-            buttons,
-            message: cli.message.clone(),
-            check: cli.check,
-            silent: cli.silent,
-            thread_id: cli.thread_id,
-            provided_api_url: cli.api_url.is_some(),
-            provided_bot_token: cli.bot_token.is_some(),
-            provided_chat_id: cli.chat_id.is_some(),
-        }))
-    }
-}
-
-fn parse_button_specs(raw: &[String]) -> Result<Vec<ButtonSpec>> {
-    let mut specs = Vec::new();
-
-    for entry in raw {
-        if entry == BUTTON_ROW_BREAK {
-            specs.push(ButtonSpec::RowBreak);
-            continue;
-        }
-
-        let mut parts = entry.splitn(2, '|');
-        let text = parts
-            .next()
-            .map(str::trim)
-            .filter(|s| !s.is_empty())
-            .ok_or_else(|| anyhow!("Invalid --button value '{}': missing label", entry))?;
-        let url = parts
-            .next()
-            .map(str::trim)
+    #[arg(
+        long = "log-file",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        help = "Append log output to a file in addition to stdout."
+    )]
+    log_file: Option<PathBuf>,
+    #[arg(
+        long = "log-format",
+        value_enum,
+        default_value_t = LogFormat::Text,
+        help = "Log line format."
+    )]
+    log_format: LogFormat,
+    #[arg(
+        long = "color",
+        value_name = "WHEN",
+        value_enum,
+        default_value_t = ColorChoice::Auto,
+        help = "Color log levels: always, auto (when stdout is a terminal) or never."
+    )]
+    color: ColorChoice,
+    #[arg(
+        short = 'q',
+        long = "quiet",
+        help = "Only print errors to stdout (log file still receives everything)."
+    )]
+    quiet: bool,
+    #[arg(
+        long = "no-progress",
+        help = "Never draw upload progress bars (implied by --quiet)."
+    )]
+    no_progress: bool,
+    #[arg(
+        long = "progress-stderr",
+        overrides_with = "progress_stdout",
+        help = "Draw upload progress bars on stderr (default)."
+    )]
+    progress_stderr: bool,
+    #[arg(
+        long = "progress-stdout",
+        overrides_with = "progress_stderr",
+        help = "Draw upload progress bars on stdout."
+    )]
+    progress_stdout: bool,
+    #[arg(
+        long = "format",
+        value_name = "FORMAT",
+        value_enum,
+        default_value_t = Formatter::Text,
+        help = "Print --show-config, --check and send results as text, json or csv."
+    )]
+    format: Formatter,
+    #[arg(
+        long = "output-json",
+        help = "Print the raw Telegram API response for each send request."
+    )]
+    output_json: bool,
+    #[arg(
+        long = "print-message-ids",
+        help = "Print the message_id of each sent message, one per line."
+    )]
+    print_message_ids: bool,
+    #[arg(
+        long = "dry-run",
+        help = "Validate inputs and log what would be sent without calling the API."
+    )]
+    dry_run: bool,
+    #[arg(
+        long = "continue-on-error",
+        help = "Keep sending remaining media after a failed upload."
+    )]
+    continue_on_error: bool,
+    #[arg(
+        long = "location",
+        alias = "send-location",
+        value_name = "LAT,LON",
+        allow_hyphen_values = true,
+        help = "Share a location given as 'latitude,longitude'."
+    )]
+    location: Option<String>,
+    #[arg(
+        long = "live-period",
+        value_name = "SECONDS",
+        requires = "location",
+        help = "Send the location as a live location for this many seconds (60-86400)."
+    )]
+    live_period: Option<u32>,
+    #[arg(
+        long = "contact",
+        alias = "send-contact",
+        value_name = "NAME|PHONE",
+        help = "Share a contact given as 'First Last|+1234567890'."
+    )]
+    contact: Option<String>,
+    #[arg(
+        long = "contact-vcard",
+        value_name = "VCARD",
+        requires = "contact",
+        help = "Additional contact data as a vCard string."
+    )]
+    contact_vcard: Option<String>,
+    #[arg(
+        long = "dice",
+        alias = "send-dice",
+        value_name = "EMOJI",
+        num_args = 0..=1,
+        default_missing_value = "🎲",
+        help = "Send an animated dice: 🎲, 🎯, 🏀, ⚽, 🎳 or 🎰 (default 🎲)."
+    )]
+    dice: Option<String>,
+    #[arg(
+        long = "send-poll",
+        value_name = "QUESTION",
+        help = "Send a poll with the given question instead of a message."
+    )]
+    send_poll: Option<String>,
+    #[arg(
+        long = "option",
+        value_name = "TEXT",
+        action = ArgAction::Append,
+        requires = "send_poll",
+        help = "Poll answer option; repeat for each option."
+    )]
+    poll_options: Vec<String>,
+    #[arg(
+        long = "poll-type",
+        value_name = "TYPE",
+        value_parser = ["regular", "quiz"],
+        default_value = "regular",
+        help = "Poll type: regular or quiz."
+    )]
+    poll_type: String,
+    #[arg(
+        long = "non-anonymous",
+        requires = "send_poll",
+        help = "Show who voted in the poll."
+    )]
+    non_anonymous: bool,
+    #[arg(
+        long = "correct-option",
+        value_name = "INDEX",
+        requires = "send_poll",
+        help = "Zero-based index of the correct option (quiz polls)."
+    )]
+    correct_option_id: Option<usize>,
+    #[arg(
+        long = "explanation",
+        value_name = "TEXT",
+        requires = "send_poll",
+        help = "Text shown after a wrong quiz answer."
+    )]
+    explanation: Option<String>,
+    #[arg(
+        long = "open-period",
+        value_name = "SECONDS",
+        requires = "send_poll",
+        help = "Seconds the poll stays open (5-600)."
+    )]
+    open_period: Option<u32>,
+    #[arg(
+        long = "pin",
+        help = "Pin the sent message (the first item of an album or part of a split message)."
+    )]
+    pin: bool,
+    #[arg(
+        long = "pin-silent",
+        requires = "pin",
+        help = "Pin without notifying chat members."
+    )]
+    pin_silent: bool,
+    #[arg(
+        long = "forward-from",
+        value_names = ["CHAT_ID", "MESSAGE_ID"],
+        num_args = 2,
+        allow_hyphen_values = true,
+        conflicts_with = "send_poll",
+        help = "Forward an existing message from another chat to the target chat."
+    )]
+    forward_from: Option<Vec<String>>,
+    #[arg(
+        long = "copy-from",
+        value_names = ["CHAT_ID", "MESSAGE_ID"],
+        num_args = 2,
+        allow_hyphen_values = true,
+        conflicts_with_all = ["send_poll", "forward_from"],
+        help = "Copy an existing message to the target chat without the forwarded-from header."
+    )]
+    copy_from: Option<Vec<String>>,
+    #[arg(
+        long = "delete-message",
+        value_name = "CHAT_ID:MESSAGE_ID",
+        allow_hyphen_values = true,
+        conflicts_with_all = ["send_poll", "forward_from", "copy_from", "delete_message_ids"],
+        help = "Delete a previously sent message and exit."
+    )]
+    delete_message: Option<String>,
+    #[arg(
+        long = "delete-message-id",
+        value_name = "MESSAGE_ID",
+        action = ArgAction::Append,
+        conflicts_with_all = ["send_poll", "forward_from", "copy_from"],
+        help = "Delete this message from the target chat; repeat to delete several at once."
+    )]
+    delete_message_ids: Vec<i64>,
+    #[arg(
+        long = "delete-chat-id",
+        value_name = "CHAT_ID",
+        allow_hyphen_values = true,
+        requires = "delete_message_ids",
+        help = "Chat to delete --delete-message-id messages from (default: the target chat)."
+    )]
+    delete_chat_id: Option<String>,
+    #[arg(
+        long = "edit-message",
+        value_name = "MESSAGE_ID",
+        conflicts_with_all = ["send_poll", "forward_from", "copy_from", "delete_message", "delete_message_ids"],
+        help = "Replace the text (or, with --caption only, the caption) of a sent message."
+    )]
+    edit_message: Option<i64>,
+    #[arg(
+        long = "protect-content",
+        help = "Stop the forwarded or copied message from being forwarded again or saved."
+    )]
+    protect_content: bool,
+    #[arg(
+        long = "message",
+        alias = "text",
+        value_name = "TEXT",
+        conflicts_with = "message",
+        help = "Message text; use '-' to read it from stdin."
+    )]
+    message_flag: Option<String>,
+    #[arg(
+        long = "command",
+        value_name = "CMD",
+        conflicts_with_all = ["message", "message_flag"],
+        help = "Run a shell command and send its stdout as the message."
+    )]
+    command: Option<String>,
+    #[arg(
+        long = "split-message",
+        help = "Split messages longer than 4096 characters into replies to each other."
+    )]
+    split_message: bool,
+    #[arg(
+        long = "entities",
+        value_name = "JSON",
+        conflicts_with = "split_message",
+        help = "Format the message with a JSON array of MessageEntity objects instead of HTML."
+    )]
+    entities: Option<String>,
+    #[arg(
+        long = "code",
+        value_name = "LANG",
+        num_args = 0..=1,
+        conflicts_with = "entities",
+        help = "Send the message as a code block, highlighted as LANG if given (escapes HTML for you)."
+    )]
+    code: Option<Option<String>>,
+    #[arg(
+        long = "spoiler-text",
+        conflicts_with = "entities",
+        help = "Hide the whole message text behind a spoiler."
+    )]
+    spoiler_text: bool,
+    #[arg(
+        long = "blockquote",
+        conflicts_with = "entities",
+        help = "Wrap the message text in a blockquote."
+    )]
+    blockquote: bool,
+    #[arg(
+        long = "expandable-blockquote",
+        conflicts_with_all = ["entities", "blockquote"],
+        help = "Wrap the message text in a collapsed blockquote that expands on tap."
+    )]
+    expandable_blockquote: bool,
+    #[arg(
+        long = "mention",
+        value_name = "USER_ID[:LABEL]",
+        action = ArgAction::Append,
+        conflicts_with = "entities",
+        help = "Mention a user by numeric ID at the start of the message, shown as LABEL or @user (repeatable)."
+    )]
+    mentions: Vec<String>,
+    #[arg(
+        long = "mention-append",
+        requires = "mentions",
+        help = "Put --mention links at the end of the message instead."
+    )]
+    mention_append: bool,
+    #[arg(
+        long = "markdown-to-html",
+        conflicts_with_all = ["entities", "code"],
+        help = "Convert the message from Markdown (CommonMark, with tables) to Telegram HTML."
+    )]
+    markdown_to_html: bool,
+    #[arg(
+        long = "message-template",
+        help = "Expand {placeholders} in the message ({datetime}, {hostname}, {username} and --var keys)."
+    )]
+    message_template: bool,
+    #[arg(
+        long = "var",
+        value_name = "KEY=VALUE",
+        action = ArgAction::Append,
+        help = "Template variable for {KEY} in the message; repeatable, implies --message-template."
+    )]
+    vars: Vec<String>,
+    #[arg(
+        long = "include-stderr",
+        requires = "command",
+        help = "Append the --command stderr output to the message."
+    )]
+    include_stderr: bool,
+    #[arg(
+        long = "on-success",
+        value_name = "CMD",
+        help = "Run a shell command after everything was sent successfully."
+    )]
+    on_success: Option<String>,
+    #[arg(
+        long = "on-failure",
+        value_name = "CMD",
+        help = "Run a shell command if sending fails; the error is in $SENDTG_ERROR."
+    )]
+    on_failure: Option<String>,
+    #[arg(
+        long = "webhook-notify",
+        value_name = "URL",
+        help = "POST a JSON report (status, chat_id, files_sent, error) to URL once sending finishes."
+    )]
+    webhook_notify: Option<String>,
+    #[arg(help = "Message text when no media is provided; use '-' to read it from stdin.")]
+    message: Option<String>,
+}
+
+impl Cli {
+    fn api_url_override(&self) -> Option<String> {
+        if self.local_api {
+            return Some(LOCAL_API_URL.to_string());
+        }
+        self.api_url.clone()
+    }
+
+    fn config_max_age(&self) -> u64 {
+        self.config_max_age
+            .unwrap_or(crate::config::DEFAULT_CONFIG_MAX_AGE_DAYS)
+    }
+
+    fn chat_id_override(&self) -> Option<String> {
+        match self.chat_username.as_deref() {
+            Some(name) => Some(format!("@{}", name.trim().trim_start_matches('@'))),
+            None => self.chat_id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Args {
+    pub api_url: String,
+    pub bot_token: String,
+    pub chat_id: String,
+    pub media_paths: Vec<PathBuf>,
+    pub spoiler: bool,
+    pub spoiler_items: Vec<usize>,
+    pub streaming: bool,
+    pub thumbnail_path: Option<PathBuf>,
+    pub skip_thumbnail: bool,
+    pub strip_exif: bool,
+    pub ffmpeg_path: Option<String>,
+    pub ffprobe_path: Option<String>,
+    pub metadata_timeout_secs: u64,
+    pub max_file_size: Option<u64>,
+    pub since: Option<std::time::SystemTime>,
+    pub no_cache: bool,
+    pub check_files: bool,
+    pub broadcast: Vec<(String, Credentials)>,
+    pub batch: Vec<crate::batch::BatchEntry>,
+    pub queue: Option<PathBuf>,
+    pub upload_chunk_size: Option<usize>,
+    pub split_message: bool,
+    pub entities: Option<serde_json::Value>,
+    pub spoiler_text: bool,
+    pub blockquote: bool,
+    pub expandable_blockquote: bool,
+    pub on_success: Option<String>,
+    pub on_failure: Option<String>,
+    pub webhook_notify: Option<String>,
+    pub thumbnail_at: Option<f64>,
+    pub thumbnail_size: (u32, u32),
+    pub delay_secs: Option<u64>,
+    pub video_note: bool,
+    pub voice: bool,
+    pub no_group: bool,
+    pub no_group_documents: bool,
+    pub no_group_photos: bool,
+    pub parallel: Option<usize>,
+    pub as_file: bool,
+    pub disable_content_type_detection: bool,
+    pub captions: Vec<String>,
+    pub caption_parse_mode: Option<String>,
+    pub prepend_filename: bool,
+    pub append_stats: bool,
+    pub buttons: Vec<ButtonSpec>,
+    pub force_reply: bool,
+    pub reply_keyboard: Option<ReplyKeyboard>,
+    pub reply_keyboard_remove: bool,
+    pub markup: Option<serde_json::Value>,
+    pub message: Option<String>,
+    pub check: bool,
+    pub silent: bool,
+    pub thread_id: Option<i64>,
+    pub output_json: bool,
+    pub print_message_ids: bool,
+    pub dry_run: bool,
+    pub continue_on_error: bool,
+    pub location: Option<(f64, f64)>,
+    pub live_period: Option<u32>,
+    pub contact: Option<ContactSpec>,
+    pub dice: Option<String>,
+    pub pin: bool,
+    pub pin_silent: bool,
+    pub provided_api_url: bool,
+    pub provided_bot_token: bool,
+    pub provided_chat_id: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub api_url: String,
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct PollArgs {
+    pub credentials: Credentials,
+    pub thread_id: Option<i64>,
+    pub silent: bool,
+    pub question: String,
+    pub options: Vec<String>,
+    pub is_anonymous: bool,
+    pub poll_type: String,
+    pub correct_option_id: Option<usize>,
+    pub explanation: Option<String>,
+    pub open_period: Option<u32>,
+    pub pin: bool,
+    pub pin_silent: bool,
+    pub dry_run: bool,
+    pub output_json: bool,
+    pub print_message_ids: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ForwardArgs {
+    pub credentials: Credentials,
+    pub thread_id: Option<i64>,
+    pub silent: bool,
+    pub protect_content: bool,
+    pub from_chat_id: String,
+    pub message_id: i64,
+    pub pin: bool,
+    pub pin_silent: bool,
+    pub dry_run: bool,
+    pub output_json: bool,
+    pub print_message_ids: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct CopyArgs {
+    pub credentials: Credentials,
+    pub thread_id: Option<i64>,
+    pub silent: bool,
+    pub protect_content: bool,
+    pub from_chat_id: String,
+    pub message_id: i64,
+    pub caption: Option<String>,
+    pub parse_mode: Option<String>,
+    pub buttons: Vec<ButtonSpec>,
+    pub pin: bool,
+    pub pin_silent: bool,
+    pub dry_run: bool,
+    pub output_json: bool,
+    pub print_message_ids: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeleteArgs {
+    pub credentials: Credentials,
+    pub chat_id: String,
+    pub message_ids: Vec<i64>,
+    pub dry_run: bool,
+    pub output_json: bool,
+    pub print_message_ids: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct EditArgs {
+    pub credentials: Credentials,
+    pub message_id: i64,
+    pub text: Option<String>,
+    pub caption: Option<String>,
+    pub parse_mode: Option<String>,
+    pub buttons: Vec<ButtonSpec>,
+    pub dry_run: bool,
+    pub output_json: bool,
+    pub print_message_ids: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueueFile {
+    pub path: PathBuf,
+    pub args: Box<Args>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportArgs {
+    pub path: PathBuf,
+    pub format: crate::config::ConfigFormat,
+}
+
+#[derive(Debug, Clone)]
+pub struct SetupArgs {
+    pub api_url: Option<String>,
+    pub bot_token: Option<String>,
+    pub chat_id: Option<String>,
+    pub config_max_age: u64,
+}
+
+#[derive(Debug, Clone)]
+pub enum ParsedArgs {
+    Run(Box<Args>),
+    Setup(SetupArgs),
+    ShowConfig { export: bool, redact: bool },
+    ValidateConfig,
+    ResetConfig { yes: bool },
+    ImportConfig(ImportArgs),
+    SetField { field: String, value: String },
+    UnsetField { field: String },
+    Completions(Shell),
+    ManPage,
+    Poll(PollArgs),
+    Forward(ForwardArgs),
+    Copy(CopyArgs),
+    Delete(DeleteArgs),
+    Edit(EditArgs),
+    CheckToken(Credentials),
+    GetChat(Credentials),
+    ExportInviteLink(Credentials),
+    FlushQueue(QueueFile),
+}
+
+impl Args {
+    pub fn metadata_options(&self) -> crate::utils::MetadataOptions {
+        let defaults = crate::utils::MetadataOptions::default();
+        crate::utils::MetadataOptions {
+            thumbnail_at: self.thumbnail_at,
+            thumbnail_size: self.thumbnail_size,
+            ffmpeg: self.ffmpeg_path.clone().unwrap_or(defaults.ffmpeg),
+            ffprobe: self.ffprobe_path.clone().unwrap_or(defaults.ffprobe),
+            timeout: std::time::Duration::from_secs(self.metadata_timeout_secs),
+        }
+    }
+
+    pub fn reply_markup(&self) -> Option<serde_json::Value> {
+        if let Some(markup) = self.markup.as_ref() {
+            return Some(markup.clone());
+        }
+        if self.force_reply {
+            return Some(serde_json::json!({ "force_reply": true, "selective": false }));
+        }
+        if self.reply_keyboard_remove {
+            return Some(serde_json::json!({ "remove_keyboard": true }));
+        }
+        if let Some(keyboard) = self.reply_keyboard.as_ref() {
+            return Some(crate::utils::create_reply_keyboard(keyboard));
+        }
+        crate::utils::create_reply_markup(&self.buttons)
+    }
+
+    pub fn has_content(&self) -> bool {
+        !self.media_paths.is_empty()
+            || self.message.is_some()
+            || self.location.is_some()
+            || self.contact.is_some()
+            || self.dice.is_some()
+    }
+
+    pub fn parse() -> Result<ParsedArgs> {
+        let matches = Cli::command().get_matches();
+        let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+        if let Some(shell) = cli.completions {
+            return Ok(ParsedArgs::Completions(shell));
+        }
+        if cli.man_page {
+            return Ok(ParsedArgs::ManPage);
+        }
+
+        crate::logger::set_log_format(cli.log_format);
+        crate::logger::set_quiet(cli.quiet);
+        crate::logger::set_color(cli.color);
+        crate::output::set_format(cli.format);
+        crate::utils::set_progress_stdout(cli.progress_stdout);
+        crate::utils::set_no_progress(cli.no_progress || cli.quiet);
+        if let Some(path) = cli.log_file.as_deref() {
+            crate::logger::init_log_file(path);
+        }
+        crate::telegram::set_test_environment(cli.test_api);
+        if !cli.no_audit
+            && let Some(path) = cli
+                .audit_log
+                .clone()
+                .or_else(|| crate::audit::default_audit_path().ok())
+        {
+            crate::audit::init(&path);
+        }
+        if cli.no_verify_ssl {
+            crate::telegram::set_accept_invalid_certs(true);
+            log_warn!(
+                "TLS certificate verification is DISABLED; anyone on the network path can read or alter requests, including the bot token"
+            );
+        }
+
+        if cli.setup {
+            return Ok(ParsedArgs::Setup(SetupArgs {
+                api_url: cli.api_url_override(),
+                bot_token: cli.bot_token.clone(),
+                chat_id: cli.chat_id_override(),
+                config_max_age: cli.config_max_age(),
+            }));
+        }
+
+        if cli.reset_config {
+            return Ok(ParsedArgs::ResetConfig { yes: cli.yes });
+        }
+
+        if let Some(path) = cli.import_config.clone() {
+            let format = match cli.import_format {
+                Some(format) => format,
+                None => match path.extension().and_then(|ext| ext.to_str()) {
+                    Some(ext) if ext.eq_ignore_ascii_case("json") => {
+                        crate::config::ConfigFormat::Json
+                    }
+                    Some(ext) if ext.eq_ignore_ascii_case("toml") => {
+                        crate::config::ConfigFormat::Toml
+                    }
+                    _ => {
+                        return Err(anyhow!(
+                            "Cannot tell the format of {}; pass --import-format json or toml",
+                            path.display()
+                        ));
+                    }
+                },
+            };
+            return Ok(ParsedArgs::ImportConfig(ImportArgs { path, format }));
+        }
+
+        if let Some([field, value]) = cli.set.as_deref() {
+            return Ok(ParsedArgs::SetField {
+                field: field.clone(),
+                value: value.clone(),
+            });
+        }
+
+        if let Some(field) = cli.unset.clone() {
+            return Ok(ParsedArgs::UnsetField { field });
+        }
+
+        if cli.validate_config {
+            return Ok(ParsedArgs::ValidateConfig);
+        }
+
+        if cli.show_config {
+            return Ok(ParsedArgs::ShowConfig {
+                export: cli.export,
+                redact: cli.redact,
+            });
+        }
+
+        if cli.check_token {
+            let file_config = crate::config::load_config()?.unwrap_or_default();
+            let api_url = cli
+                .api_url_override()
+                .or(file_config.api_url)
+                .ok_or_else(|| anyhow!("API URL is missing; pass --api_url or run --setup"))?;
+            let bot_token = cli
+                .bot_token
+                .clone()
+                .or(file_config.bot_token)
+                .ok_or_else(|| anyhow!("Bot token is missing; pass --bot_token or run --setup"))?;
+            return Ok(ParsedArgs::CheckToken(Credentials {
+                api_url,
+                bot_token,
+                chat_id: String::new(),
+            }));
+        }
+
+        if cli.queue.is_some() {
+            reject_unqueueable_flags(&matches)?;
+        }
+
+        let file_config = load_file_config()?;
+        crate::config::warn_if_stale(cli.config_max_age());
+        let Credentials {
+            api_url,
+            bot_token,
+            chat_id,
+        } = resolve_credentials(&cli, &file_config)?;
+
+        if cli.get_chat {
+            return Ok(ParsedArgs::GetChat(Credentials {
+                api_url,
+                bot_token,
+                chat_id,
+            }));
+        }
+
+        if cli.export_invite_link {
+            return Ok(ParsedArgs::ExportInviteLink(Credentials {
+                api_url,
+                bot_token,
+                chat_id,
+            }));
+        }
+
+        if let Some(question) = cli.send_poll.clone() {
+            let poll = PollArgs {
+                credentials: Credentials {
+                    api_url,
+                    bot_token,
+                    chat_id,
+                },
+                thread_id: cli.thread_id,
+                silent: cli.silent,
+                question,
+                options: cli.poll_options.clone(),
+                is_anonymous: !cli.non_anonymous,
+                poll_type: cli.poll_type.clone(),
+                correct_option_id: cli.correct_option_id,
+                explanation: cli.explanation.clone(),
+                open_period: cli.open_period,
+                pin: cli.pin,
+                pin_silent: cli.pin_silent,
+                dry_run: cli.dry_run,
+                output_json: cli.output_json,
+                print_message_ids: cli.print_message_ids,
+            };
+            validate_poll(&poll)?;
+            return Ok(ParsedArgs::Poll(poll));
+        }
+
+        if let Some(raw) = cli.delete_message.as_deref() {
+            let (target, message_id) = parse_chat_message(raw)?;
+            return Ok(ParsedArgs::Delete(DeleteArgs {
+                credentials: Credentials {
+                    api_url,
+                    bot_token,
+                    chat_id,
+                },
+                chat_id: target,
+                message_ids: vec![message_id],
+                dry_run: cli.dry_run,
+                output_json: cli.output_json,
+                print_message_ids: cli.print_message_ids,
+            }));
+        }
+        if !cli.delete_message_ids.is_empty() {
+            let target = cli
+                .delete_chat_id
+                .clone()
+                .unwrap_or_else(|| chat_id.clone());
+            return Ok(ParsedArgs::Delete(DeleteArgs {
+                credentials: Credentials {
+                    api_url,
+                    bot_token,
+                    chat_id,
+                },
+                chat_id: target,
+                message_ids: cli.delete_message_ids.clone(),
+                dry_run: cli.dry_run,
+                output_json: cli.output_json,
+                print_message_ids: cli.print_message_ids,
+            }));
+        }
+
+        if let Some(values) = cli.forward_from.as_deref() {
+            let (from_chat_id, message_id) = parse_message_ref(values, "--forward-from")?;
+            return Ok(ParsedArgs::Forward(ForwardArgs {
+                credentials: Credentials {
+                    api_url,
+                    bot_token,
+                    chat_id,
+                },
+                thread_id: cli.thread_id,
+                silent: cli.silent,
+                protect_content: cli.protect_content,
+                from_chat_id,
+                message_id,
+                pin: cli.pin,
+                pin_silent: cli.pin_silent,
+                dry_run: cli.dry_run,
+                output_json: cli.output_json,
+                print_message_ids: cli.print_message_ids,
+            }));
+        }
+
+        let mut buttons = collect_buttons(&cli, &matches)?;
+
+        match (&cli.button_text, &cli.button_url) {
+            (Some(text), Some(url)) => {
+                validate_button_url(url)
+                    .map_err(|err| anyhow!("Invalid --button-url value '{}': {}", url, err))?;
+                buttons.push(ButtonSpec::Link {
+                    text: text.clone(),
+                    url: url.clone(),
+                });
+            }
+            (Some(_), None) | (None, Some(_)) => {
+                return Err(anyhow!(
+                    "Both --button-text and --button-url must be provided together, or use --button \"Label|URL\"."
+                ));
+            }
+            (None, None) => {}
+        }
+
+        if let Some(values) = cli.copy_from.as_deref() {
+            let (from_chat_id, message_id) = parse_message_ref(values, "--copy-from")?;
+            return Ok(ParsedArgs::Copy(CopyArgs {
+                credentials: Credentials {
+                    api_url,
+                    bot_token,
+                    chat_id,
+                },
+                thread_id: cli.thread_id,
+                silent: cli.silent,
+                protect_content: cli.protect_content,
+                from_chat_id,
+                message_id,
+                caption: cli.captions.first().cloned(),
+                parse_mode: cli.caption_parse_mode.clone(),
+                buttons,
+                pin: cli.pin,
+                pin_silent: cli.pin_silent,
+                dry_run: cli.dry_run,
+                output_json: cli.output_json,
+                print_message_ids: cli.print_message_ids,
+            }));
+        }
+
+        let excludes = cli
+            .exclude
+            .iter()
+            .map(|raw| {
+                glob::Pattern::new(raw)
+                    .map_err(|err| anyhow!("Invalid --exclude pattern '{}': {}", raw, err))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut media_paths = expand_media_globs(&cli.media, &excludes)?;
+        if let Some(list) = cli.media_file.as_deref() {
+            media_paths.extend(read_media_list(list)?);
+        }
+        if let Some(dir) = cli.album_dir.as_deref() {
+            let mut found = Vec::new();
+            scan_media_dir(dir, cli.recursive, &excludes, &mut found)?;
+            if found.is_empty() {
+                return Err(anyhow!(
+                    "No photos, videos or audio found in {}",
+                    dir.display()
+                ));
+            }
+            sort_media_paths(&mut found, SortBy::Name);
+            media_paths.extend(found);
+        }
+        if let Some(sort_by) = cli.sort_by {
+            sort_media_paths(&mut media_paths, sort_by);
+        }
+        if cli.reverse {
+            media_paths.reverse();
+        }
+
+        let stdin_media = media_paths
+            .iter()
+            .filter(|path| path.as_os_str() == STDIN_SENTINEL)
+            .count();
+        if stdin_media > 1 {
+            return Err(anyhow!("--media - can only be given once"));
+        }
+        if stdin_media == 1 {
+            if cli.video_note || cli.voice {
+                return Err(anyhow!(
+                    "--media - cannot be combined with --video-note or --voice"
+                ));
+            }
+            if cli.message_flag.as_deref().or(cli.message.as_deref()) == Some(STDIN_SENTINEL) {
+                return Err(anyhow!(
+                    "stdin cannot provide both the media and the message"
+                ));
+            }
+        }
+
+        if let Some(&n) = cli
+            .spoiler_nth
+            .iter()
+            .find(|&&n| n == 0 || n > media_paths.len())
+        {
+            return Err(anyhow!(
+                "--spoiler-nth {} is out of range for {} media item(s)",
+                n,
+                media_paths.len()
+            ));
+        }
+
+        let mut captions = cli.captions.clone();
+        if let Some(path) = cli.caption_file.as_deref() {
+            let content = std::fs::read_to_string(path).map_err(|err| {
+                anyhow!("Failed to read caption file {}: {}", path.display(), err)
+            })?;
+            let caption = if cli.no_trim {
+                content
+            } else {
+                content.trim_end().to_string()
+            };
+            captions.push(caption);
+        }
+
+        if let Some(path) = cli.thumbnail.as_deref() {
+            validate_thumbnail(path)?;
+        }
+        if let Some(at) = cli.thumbnail_at
+            && (!at.is_finite() || at < 0.0)
+        {
+            return Err(anyhow!(
+                "--thumbnail-at must be a non-negative number of seconds"
+            ));
+        }
+
+        let thumbnail_size = match cli.thumbnail_size.as_deref() {
+            Some(raw) => parse_thumbnail_size(raw)?,
+            None => crate::utils::DEFAULT_THUMBNAIL_SIZE,
+        };
+
+        let metadata_timeout_secs = cli
+            .metadata_timeout
+            .unwrap_or(crate::utils::DEFAULT_METADATA_TIMEOUT_SECS);
+        if metadata_timeout_secs == 0 {
+            return Err(anyhow!("--metadata-timeout must be at least 1 second"));
+        }
+
+        if let Some(url) = cli.webhook_notify.as_deref() {
+            validate_webhook_url(url)
+                .map_err(|err| anyhow!("Invalid --webhook-notify value '{}': {}", url, err))?;
+        }
+
+        let max_file_size = cli
+            .max_file_size
+            .as_deref()
+            .map(|raw| parse_byte_size(raw, "--max-file-size"))
+            .transpose()?;
+        let broadcast = if cli.broadcast {
+            broadcast_targets(&cli, &file_config)?
+        } else {
+            Vec::new()
+        };
+        let batch = match (cli.batch_csv.as_deref(), cli.batch_json.as_deref()) {
+            (Some(path), _) => crate::batch::read_csv(path)?,
+            (None, Some(path)) => crate::batch::read_json(path)?,
+            (None, None) => Vec::new(),
+        };
+        let since = cli.since.as_deref().map(parse_since).transpose()?;
+        let upload_chunk_size = if cli.large_file || file_config.large_file_mode.unwrap_or(false) {
+            let bytes = match cli.chunk_size.as_deref() {
+                Some(raw) => parse_byte_size(raw, "--chunk-size")?,
+                None => crate::utils::DEFAULT_UPLOAD_CHUNK_SIZE,
+            };
+            Some(usize::try_from(bytes).map_err(|_| anyhow!("--chunk-size is too large"))?)
+        } else {
+            None
+        };
+
+        let message = match cli.message_flag.clone().or_else(|| cli.message.clone()) {
+            Some(text) if text == STDIN_SENTINEL => Some(read_message_from_stdin()?),
+            Some(text) => Some(text),
+            None => cli
+                .command
+                .as_deref()
+                .map(|command| crate::utils::run_shell_command(command, cli.include_stderr))
+                .transpose()?,
+        };
+        let message = if cli.message_template || !cli.vars.is_empty() {
+            let vars = cli
+                .vars
+                .iter()
+                .map(|raw| parse_template_var(raw))
+                .collect::<Result<Vec<_>>>()?;
+            message.map(|text| crate::utils::expand_template(&text, &vars))
+        } else {
+            message
+        };
+        let message = if cli.markdown_to_html {
+            message.map(|text| crate::markdown::markdown_to_html(&text))
+        } else {
+            message
+        };
+        let message = match cli.code.as_ref() {
+            Some(language) => {
+                let text = message.ok_or_else(|| anyhow!("--code needs a message to format"))?;
+                Some(format_code_block(&text, language.as_deref())?)
+            }
+            None => message,
+        };
+        let message = if cli.mentions.is_empty() {
+            message
+        } else {
+            let mentions = cli
+                .mentions
+                .iter()
+                .map(|raw| parse_mention(raw))
+                .collect::<Result<Vec<_>>>()?
+                .join(" ");
+            Some(match message {
+                Some(text) if cli.mention_append => format!("{}\n{}", text, mentions),
+                Some(text) => format!("{} {}", mentions, text),
+                None => mentions,
+            })
+        };
+
+        if let Some(message_id) = cli.edit_message {
+            let caption = captions.first().cloned();
+            let parse_mode = if message.is_some() {
+                Some("HTML".to_string())
+            } else if caption.is_some() {
+                cli.caption_parse_mode.clone()
+            } else {
+                return Err(anyhow!(
+                    "--edit-message needs new message text or a --caption"
+                ));
+            };
+            return Ok(ParsedArgs::Edit(EditArgs {
+                credentials: Credentials {
+                    api_url,
+                    bot_token,
+                    chat_id,
+                },
+                message_id,
+                text: message,
+                caption,
+                parse_mode,
+                buttons,
+                dry_run: cli.dry_run,
+                output_json: cli.output_json,
+                print_message_ids: cli.print_message_ids,
+            }));
+        }
+
+        let location = cli.location.as_deref().map(parse_location).transpose()?;
+        if let Some(period) = cli.live_period
+            && !(60..=86400).contains(&period)
+        {
+            return Err(anyhow!(
+                "--live-period must be between 60 and 86400 seconds"
+            ));
+        }
+
+        let contact = cli
+            .contact
+            .as_deref()
+            .map(|raw| parse_contact(raw, cli.contact_vcard.clone()))
+            .transpose()?;
+
+        let dice = cli.dice.as_deref().map(parse_dice).transpose()?;
+
+        let markup = cli
+            .markup_file
+            .as_deref()
+            .map(read_markup_file)
+            .transpose()?;
+
+        let entities = cli.entities.as_deref().map(parse_entities).transpose()?;
+
+        let reply_keyboard = cli
+            .reply_keyboard
+            .as_deref()
+            .map(parse_reply_keyboard)
+            .transpose()?
+            .map(|rows| ReplyKeyboard {
+                rows,
+                one_time: cli.one_time_keyboard,
+                resize: cli.resize_keyboard,
+            });
+
+        // Generated caption lines are HTML, so plain captions are escaped to keep
+        // their text unchanged once the parse mode switches to HTML.
+        let mut caption_parse_mode = cli.caption_parse_mode.clone();
+        if cli.prepend_filename || cli.append_stats {
+            match caption_parse_mode.as_deref() {
+                None => {
+                    captions = captions
+                        .iter()
+                        .map(|caption| crate::utils::escape_html(caption))
+                        .collect();
+                    caption_parse_mode = Some("HTML".to_string());
+                }
+                Some("HTML") => {}
+                Some(mode) => {
+                    return Err(anyhow!(
+                        "--prepend-filename and --append-stats need HTML captions, not --caption-parse-mode {}",
+                        mode
+                    ));
+                }
+            }
+        }
+
+        let args = Box::new(Args {
+            api_url,
+            bot_token,
+            chat_id,
+            media_paths,
+            spoiler: cli.spoiler,
+            spoiler_items: cli.spoiler_nth.clone(),
+            streaming: cli.streaming,
+            thumbnail_path: cli.thumbnail.clone(),
+            skip_thumbnail: cli.skip_thumbnail,
+            strip_exif: cli.strip_exif,
+            ffmpeg_path: cli
+                .ffmpeg_path
+                .clone()
+                .or_else(|| file_config.ffmpeg_path.clone()),
+            ffprobe_path: cli
+                .ffprobe_path
+                .clone()
+                .or_else(|| file_config.ffprobe_path.clone()),
+            metadata_timeout_secs,
+            max_file_size,
+            since,
+            no_cache: cli.no_cache,
+            check_files: cli.check_files,
+            broadcast,
+            batch,
+            queue: cli.queue.clone(),
+            upload_chunk_size,
+            split_message: cli.split_message,
+            entities,
+            spoiler_text: cli.spoiler_text,
+            blockquote: cli.blockquote,
+            expandable_blockquote: cli.expandable_blockquote,
+            on_success: cli.on_success.clone(),
+            on_failure: cli.on_failure.clone(),
+            webhook_notify: cli.webhook_notify.clone(),
+            thumbnail_at: cli.thumbnail_at,
+            thumbnail_size,
+            delay_secs: cli.delay_secs,
+            video_note: cli.video_note,
+            voice: cli.voice,
+            no_group: cli.no_group,
+            no_group_documents: cli.no_group_documents,
+            no_group_photos: cli.no_group_photos,
+            parallel: cli.parallel.map(usize::from),
+            as_file: cli.as_file,
+            disable_content_type_detection: cli.disable_content_type_detection,
+            captions,
+            caption_parse_mode,
+            prepend_filename: cli.prepend_filename,
+            append_stats: cli.append_stats,
+            buttons,
+            force_reply: cli.force_reply,
+            reply_keyboard,
+            reply_keyboard_remove: cli.reply_keyboard_remove,
+            markup,
+            message,
+            check: cli.check,
+            silent: cli.silent,
+            thread_id: cli.thread_id,
+            output_json: cli.output_json,
+            print_message_ids: cli.print_message_ids,
+            dry_run: cli.dry_run,
+            continue_on_error: cli.continue_on_error,
+            location,
+            live_period: cli.live_period,
+            contact,
+            dice,
+            pin: cli.pin,
+            pin_silent: cli.pin_silent,
+            provided_api_url: cli.api_url_override().is_some(),
+            provided_bot_token: cli.bot_token.is_some(),
+            provided_chat_id: cli.chat_id_override().is_some(),
+        });
+        match cli.flush_queue.clone() {
+            Some(path) => Ok(ParsedArgs::FlushQueue(QueueFile { path, args })),
+            None => Ok(ParsedArgs::Run(args)),
+        }
+    }
+}
+
+pub fn write_completions(shell: Shell) -> Result<()> {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut Cli::command(), "sendtg", &mut script);
+    io::stdout()
+        .write_all(&script)
+        .map_err(|err| anyhow!("Failed to write completion script: {}", err))
+}
+
+const MAN_EXTRA_SECTIONS: &str = r#".SH FILES
+.TP
+\fI$HOME/.config/sendtg/config.toml\fR
+TOML file written by \fB\-\-setup\fR. Flags override the stored values.
+On macOS the file lives at \fI~/Library/Application Support/sendtg/config.toml\fR.
+On Windows the file lives at \fI%APPDATA%\\sendtg\\config.toml\fR.
+.PP
+.nf
+api_url = "https://api.telegram.org/bot"
+bot_token = "123456:ABC\-DEF"
+chat_id = "\-1001234567890"
+ffmpeg_path = "/usr/local/bin/ffmpeg"    # optional
+ffprobe_path = "/usr/local/bin/ffprobe"  # optional
+large_file_mode = true                   # optional, same as \-\-large\-file
+
+[profiles.ops]                           # optional, used by \-\-broadcast
+chat_id = "\-1009876543210"
+.fi
+.SH ENVIRONMENT
+.TP
+\fBHOME\fR
+Used to locate the configuration file.
+.TP
+\fBPATH\fR
+Searched for \fBffmpeg\fR and \fBffprobe\fR unless their paths are configured.
+.SH EXAMPLES
+.TP
+sendtg "Hello, world!"
+Send a text message to the configured chat.
+.TP
+sendtg \-m photo1.jpg photo2.jpg \-\-caption "Weekend recap"
+Send two photos as an album with a caption.
+.TP
+sendtg \-c \-1001234567890 \-\-thread\-id 42 "Hello topic"
+Send a message to a forum topic.
+.TP
+sendtg \-\-check
+Verify the bot token and measure API latency.
+"#;
+
+pub fn write_man_page() -> Result<()> {
+    let command = Cli::command()
+        .name("sendtg")
+        .version(env!("CARGO_PKG_VERSION"));
+    let man = clap_mangen::Man::new(command);
+    let mut page = Vec::new();
+    man.render(&mut page)?;
+    page.extend_from_slice(MAN_EXTRA_SECTIONS.as_bytes());
+    io::stdout()
+        .write_all(&page)
+        .map_err(|err| anyhow!("Failed to write man page: {}", err))
+}
+
+fn load_file_config() -> Result<FileConfig> {
+    let file_config = crate::config::load_config()?;
+    let path = crate::config::config_file_path()?;
+
+    let file_config: FileConfig = match file_config {
+        Some(cfg) => cfg,
+        None => {
+            return Err(anyhow!(
+                "Configuration not found at {}. Run `sendtg --setup` first.",
+                path.display()
+            ));
+        }
+    };
+
+    if !file_config.has_required_fields() {
+        return Err(anyhow!(
+            "Configuration at {} is missing required fields. Run `sendtg --setup` to populate it.",
+            path.display()
+        ));
+    }
+
+    Ok(file_config)
+}
+
+fn resolve_credentials(cli: &Cli, file_config: &FileConfig) -> Result<Credentials> {
+    let api_url = cli
+        .api_url_override()
+        .or_else(|| file_config.api_url.clone())
+        .ok_or_else(|| anyhow!("API URL is missing from configuration"))?;
+    let bot_token = cli
+        .bot_token
+        .clone()
+        .or_else(|| file_config.bot_token.clone())
+        .ok_or_else(|| anyhow!("Bot token is missing from configuration"))?;
+    let chat_id = cli
+        .chat_id_override()
+        .or_else(|| file_config.chat_id.clone())
+        .ok_or_else(|| anyhow!("Chat ID is missing from configuration"))?;
+
+    Ok(Credentials {
+        api_url,
+        bot_token,
+        chat_id,
+    })
+}
+
+fn broadcast_targets(cli: &Cli, file_config: &FileConfig) -> Result<Vec<(String, Credentials)>> {
+    if file_config.profiles.is_empty() {
+        return Err(anyhow!(
+            "--broadcast needs at least one [profiles.<name>] table in the config file"
+        ));
+    }
+    file_config
+        .profiles
+        .iter()
+        .map(|(name, profile)| {
+            let api_url = cli
+                .api_url_override()
+                .or_else(|| profile.api_url.clone())
+                .or_else(|| file_config.api_url.clone())
+                .ok_or_else(|| anyhow!("Profile '{}' has no API URL", name))?;
+            let bot_token = cli
+                .bot_token
+                .clone()
+                .or_else(|| profile.bot_token.clone())
+                .or_else(|| file_config.bot_token.clone())
+                .ok_or_else(|| anyhow!("Profile '{}' has no bot token", name))?;
+            let chat_id = profile
+                .chat_id
+                .clone()
+                .ok_or_else(|| anyhow!("Profile '{}' has no chat_id", name))?;
+            Ok((
+                name.clone(),
+                Credentials {
+                    api_url,
+                    bot_token,
+                    chat_id,
+                },
+            ))
+        })
+        .collect()
+}
+
+fn parse_message_ref(values: &[String], flag: &str) -> Result<(String, i64)> {
+    let [chat_id, message_id] = values else {
+        return Err(anyhow!("{} expects CHAT_ID MESSAGE_ID", flag));
+    };
+    let chat_id = chat_id.trim();
+    if chat_id.is_empty() {
+        return Err(anyhow!("{} needs a non-empty chat ID", flag));
+    }
+    let message_id = message_id
+        .trim()
+        .parse::<i64>()
+        .map_err(|_| anyhow!("{}: invalid message ID '{}'", flag, message_id))?;
+    Ok((chat_id.to_string(), message_id))
+}
+
+fn parse_chat_message(raw: &str) -> Result<(String, i64)> {
+    let (chat_id, message_id) = raw
+        .rsplit_once(':')
+        .ok_or_else(|| anyhow!("Expected CHAT_ID:MESSAGE_ID, got '{}'", raw))?;
+    parse_message_ref(
+        &[chat_id.to_string(), message_id.to_string()],
+        "--delete-message",
+    )
+}
+
+fn validate_poll(poll: &PollArgs) -> Result<()> {
+    if poll.question.trim().is_empty() {
+        return Err(anyhow!("Poll question must not be empty"));
+    }
+    if !(2..=10).contains(&poll.options.len()) {
+        return Err(anyhow!(
+            "A poll needs between 2 and 10 --option values, got {}",
+            poll.options.len()
+        ));
+    }
+    if let Some(id) = poll.correct_option_id
+        && id >= poll.options.len()
+    {
+        return Err(anyhow!(
+            "--correct-option {} is out of range for {} options",
+            id,
+            poll.options.len()
+        ));
+    }
+    if poll.poll_type == "quiz" && poll.correct_option_id.is_none() {
+        return Err(anyhow!("Quiz polls require --correct-option"));
+    }
+    if poll.poll_type != "quiz" && (poll.correct_option_id.is_some() || poll.explanation.is_some())
+    {
+        return Err(anyhow!(
+            "--correct-option and --explanation are only valid with --poll-type quiz"
+        ));
+    }
+    if let Some(period) = poll.open_period
+        && !(5..=600).contains(&period)
+    {
+        return Err(anyhow!("--open-period must be between 5 and 600 seconds"));
+    }
+    Ok(())
+}
+
+fn parse_location(raw: &str) -> Result<(f64, f64)> {
+    let (lat, lon) = raw
+        .split_once(',')
+        .ok_or_else(|| anyhow!("Invalid --location '{}': expected 'LAT,LON'", raw))?;
+    let lat: f64 = lat
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("Invalid latitude in --location '{}'", raw))?;
+    let lon: f64 = lon
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("Invalid longitude in --location '{}'", raw))?;
+
+    if !(-90.0..=90.0).contains(&lat) {
+        return Err(anyhow!("Latitude {} is outside [-90, 90]", lat));
+    }
+    if !(-180.0..=180.0).contains(&lon) {
+        return Err(anyhow!("Longitude {} is outside [-180, 180]", lon));
+    }
+    Ok((lat, lon))
+}
+
+fn parse_contact(raw: &str, vcard: Option<String>) -> Result<ContactSpec> {
+    let (name, phone) = raw
+        .split_once('|')
+        .ok_or_else(|| anyhow!("Invalid --contact '{}': expected 'NAME|PHONE'", raw))?;
+    let phone = phone.trim();
+    if phone.is_empty() {
+        return Err(anyhow!("Invalid --contact '{}': missing phone number", raw));
+    }
+
+    let mut names = name.trim().splitn(2, char::is_whitespace);
+    let first_name = names
+        .next()
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| anyhow!("Invalid --contact '{}': missing name", raw))?;
+    let last_name = names
+        .next()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string);
+
+    Ok(ContactSpec {
+        first_name: first_name.to_string(),
+        last_name,
+        phone: phone.to_string(),
+        vcard,
+    })
+}
+
+fn parse_mention(raw: &str) -> Result<String> {
+    let (id, label) = match raw.split_once(':') {
+        Some((id, label)) => (id, label.trim()),
+        None => (raw, ""),
+    };
+    let id: i64 = id
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("Invalid --mention '{}': expected USER_ID[:LABEL]", raw))?;
+    let label = if label.is_empty() { "@user" } else { label };
+    Ok(format!(
+        "<a href=\"tg://user?id={}\">{}</a>",
+        id,
+        crate::utils::escape_html(label)
+    ))
+}
+
+fn parse_dice(raw: &str) -> Result<String> {
+    // Emoji keyboards often append a variation selector (U+FE0F) to ⚽.
+    let emoji = raw.trim().trim_end_matches('\u{FE0F}');
+    if DICE_EMOJIS.contains(&emoji) {
+        Ok(emoji.to_string())
+    } else {
+        Err(anyhow!(
+            "Invalid --dice '{}': expected one of {}",
+            raw,
+            DICE_EMOJIS.join(" ")
+        ))
+    }
+}
+
+fn validate_thumbnail(path: &Path) -> Result<()> {
+    if !path.is_file() {
+        return Err(anyhow!("Thumbnail not found: {}", path.display()));
+    }
+    match crate::utils::detect_mime_type(path).as_deref() {
+        Some("image/jpeg" | "image/png") => Ok(()),
+        other => Err(anyhow!(
+            "Thumbnail {} must be a JPEG or PNG image, got {}",
+            path.display(),
+            other.unwrap_or("an unknown type")
+        )),
+    }
+}
+
+fn parse_thumbnail_size(raw: &str) -> Result<(u32, u32)> {
+    let (width, height) = raw
+        .split_once(['x', 'X'])
+        .ok_or_else(|| anyhow!("Invalid --thumbnail-size '{}': expected 'WxH'", raw))?;
+    let parse = |value: &str| {
+        value
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|v| *v > 0)
+            .ok_or_else(|| anyhow!("Invalid --thumbnail-size '{}': expected 'WxH'", raw))
+    };
+    Ok((parse(width)?, parse(height)?))
+}
+
+fn parse_since(raw: &str) -> Result<std::time::SystemTime> {
+    let date = chrono::NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d")
+        .map_err(|_| anyhow!("Invalid --since '{}': expected YYYY-MM-DD", raw))?;
+    let start = date
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(chrono::Local).earliest())
+        .ok_or_else(|| anyhow!("Invalid --since '{}': no such local time", raw))?;
+    Ok(start.into())
+}
+
+fn parse_template_var(raw: &str) -> Result<(String, String)> {
+    let (key, value) = raw
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Invalid --var '{}': expected KEY=VALUE", raw))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(anyhow!("Invalid --var '{}': key is empty", raw));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+fn parse_byte_size(raw: &str, flag: &str) -> Result<u64> {
+    let trimmed = raw.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (digits, suffix) = trimmed.split_at(split);
+    let multiplier: u64 = match suffix.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1024,
+        "M" | "MB" | "MIB" => 1024 * 1024,
+        "G" | "GB" | "GIB" => 1024 * 1024 * 1024,
+        _ => return Err(anyhow!("Invalid {} '{}': unknown unit", flag, raw)),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .filter(|value| *value > 0)
+        .and_then(|value| value.checked_mul(multiplier))
+        .ok_or_else(|| anyhow!("Invalid {} '{}': expected a positive size", flag, raw))
+}
+
+fn read_media_list(path: &Path) -> Result<Vec<PathBuf>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| anyhow!("Failed to read media list {}: {}", path.display(), err))?;
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let entry = PathBuf::from(line);
+            if entry.is_absolute() {
+                entry
+            } else {
+                base.join(entry)
+            }
+        })
+        .collect())
+}
+
+fn is_excluded(path: &Path, excludes: &[glob::Pattern]) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy())
+        .is_some_and(|name| excludes.iter().any(|pattern| pattern.matches(&name)))
+}
+
+fn expand_media_globs(entries: &[PathBuf], excludes: &[glob::Pattern]) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let pattern = entry.to_string_lossy();
+        if !pattern.contains(['*', '?', '[']) || entry.exists() {
+            paths.push(entry.clone());
+            continue;
+        }
+        let matches = glob::glob(&pattern)
+            .map_err(|err| anyhow!("Invalid --media pattern '{}': {}", pattern, err))?
+            .filter_map(|result| result.ok())
+            .filter(|path| path.is_file())
+            .collect::<Vec<_>>();
+        let before = matches.len();
+        let matches: Vec<PathBuf> = matches
+            .into_iter()
+            .filter(|path| !is_excluded(path, excludes))
+            .collect();
+        if matches.is_empty() && before > 0 {
+            return Err(anyhow!(
+                "--media pattern '{}' matched only excluded files",
+                pattern
+            ));
+        }
+        if matches.is_empty() {
+            return Err(anyhow!("--media pattern '{}' matched no files", pattern));
+        }
+        paths.extend(matches);
+    }
+    Ok(paths)
+}
+
+fn scan_media_dir(
+    dir: &Path,
+    recursive: bool,
+    excludes: &[glob::Pattern],
+    found: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|err| anyhow!("Failed to read directory {}: {}", dir.display(), err))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|err| anyhow!("Failed to read directory {}: {}", dir.display(), err))?
+            .path();
+        if is_excluded(&path, excludes) {
+            continue;
+        }
+        if path.is_dir() {
+            if recursive {
+                scan_media_dir(&path, recursive, excludes, found)?;
+            }
+            continue;
+        }
+        let mime_type = crate::utils::detect_mime_type(&path);
+        if matches!(
+            crate::utils::determine_media_type(mime_type.as_deref()),
+            "photo" | "video" | "animation" | "audio"
+        ) {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn sort_media_paths(paths: &mut [PathBuf], sort_by: SortBy) {
+    match sort_by {
+        SortBy::Name => paths.sort(),
+        SortBy::Size => paths.sort_by_key(|path| {
+            std::fs::metadata(path)
+                .map(|metadata| metadata.len())
+                .unwrap_or(0)
+        }),
+        SortBy::Mtime => paths.sort_by_key(|path| {
+            std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        }),
+    }
+}
+
+fn read_message_from_stdin() -> Result<String> {
+    let mut stdin = io::stdin();
+    if stdin.is_terminal() {
+        eprintln!("Enter message text, then press Ctrl-D to send:");
+    }
+
+    let mut text = String::new();
+    stdin
+        .read_to_string(&mut text)
+        .map_err(|err| anyhow!("Failed to read message from stdin: {}", err))?;
+
+    let text = text.trim_end_matches(['\n', '\r']).to_string();
+    if text.trim().is_empty() {
+        return Err(anyhow!("Message read from stdin is empty"));
+    }
+    Ok(text)
+}
+
+/// Flags a queue entry keeps (or that only shape the text before it is
+/// queued); anything else would be silently dropped when the queue is flushed.
+const QUEUE_FLAGS: &[&str] = &[
+    "queue",
+    "chat_id",
+    "chat_username",
+    "message",
+    "message_flag",
+    "command",
+    "include_stderr",
+    "message_template",
+    "vars",
+    "markdown_to_html",
+    "code",
+    "mentions",
+    "mention_append",
+    "media",
+    "media_file",
+    "album_dir",
+    "recursive",
+    "sort_by",
+    "reverse",
+    "exclude",
+    "captions",
+    "caption_file",
+    "silent",
+    "config_max_age",
+    "audit_log",
+    "no_audit",
+    "log_file",
+    "log_format",
+    "color",
+    "quiet",
+];
+
+fn reject_unqueueable_flags(matches: &ArgMatches) -> Result<()> {
+    let command = Cli::command();
+    for id in matches.ids().map(|id| id.as_str()) {
+        if QUEUE_FLAGS.contains(&id) || matches.value_source(id) != Some(ValueSource::CommandLine) {
+            continue;
+        }
+        // Group ids (such as the one clap derives for `Cli`) are not flags.
+        let Some(arg) = command.get_arguments().find(|arg| arg.get_id() == id) else {
+            continue;
+        };
+        let flag = arg.get_long().unwrap_or(id);
+        return Err(anyhow!(
+            "--{} cannot be combined with --queue; queued entries keep only the chat, message, media, first caption and --silent",
+            flag
+        ));
+    }
+    Ok(())
+}
+
+/// Merges `--button` and `--callback` entries back into command-line order so rows
+/// and row breaks apply to both kinds.
+fn collect_buttons(cli: &Cli, matches: &ArgMatches) -> Result<Vec<ButtonSpec>> {
+    let indices = |id: &str| -> Vec<usize> {
+        matches
+            .indices_of(id)
+            .map(|indices| indices.collect())
+            .unwrap_or_default()
+    };
+    let link_indices = indices("buttons");
+    let callback_indices = indices("callbacks");
+
+    let mut entries: Vec<(usize, ButtonSpec)> = Vec::new();
+    for (position, spec) in parse_button_specs(&cli.buttons)?.into_iter().enumerate() {
+        let index = link_indices.get(position).copied().unwrap_or(usize::MAX);
+        entries.push((index, spec));
+    }
+    for (position, raw) in cli.callbacks.iter().enumerate() {
+        let index = callback_indices
+            .get(position)
+            .copied()
+            .unwrap_or(usize::MAX);
+        entries.push((index, parse_callback_spec(raw)?));
+    }
+    entries.sort_by_key(|(index, _)| *index);
+
+    Ok(entries.into_iter().map(|(_, spec)| spec).collect())
+}
+
+fn read_markup_file(path: &Path) -> Result<serde_json::Value> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| anyhow!("Failed to read markup file {}: {}", path.display(), err))?;
+    let markup: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|err| anyhow!("Invalid JSON in markup file {}: {}", path.display(), err))?;
+    if !markup.is_object() {
+        return Err(anyhow!(
+            "Markup file {} must contain a JSON object such as {{\"inline_keyboard\": [...]}}",
+            path.display()
+        ));
+    }
+    Ok(markup)
+}
+
+fn format_code_block(text: &str, language: Option<&str>) -> Result<String> {
+    let code = crate::utils::escape_html(text);
+    match language
+        .map(str::trim)
+        .filter(|language| !language.is_empty())
+    {
+        Some(language) => {
+            if !language
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '#' | '_' | '.'))
+            {
+                return Err(anyhow!("Invalid --code language '{}'", language));
+            }
+            Ok(format!(
+                "<pre><code class=\"language-{}\">{}</code></pre>",
+                language, code
+            ))
+        }
+        None => Ok(format!("<code>{}</code>", code)),
+    }
+}
+
+fn parse_entities(raw: &str) -> Result<serde_json::Value> {
+    let entities: serde_json::Value =
+        serde_json::from_str(raw).map_err(|err| anyhow!("Invalid JSON in --entities: {}", err))?;
+    let valid = entities.as_array().is_some_and(|items| {
+        items.iter().all(|item| {
+            item["type"].is_string() && item["offset"].is_u64() && item["length"].is_u64()
+        })
+    });
+    if !valid {
+        return Err(anyhow!(
+            "--entities must be a JSON array of objects with type, offset and length, such as [{{\"type\":\"bold\",\"offset\":0,\"length\":5}}]"
+        ));
+    }
+    Ok(entities)
+}
+
+fn parse_reply_keyboard(raw: &str) -> Result<Vec<Vec<String>>> {
+    let rows: Vec<Vec<String>> = raw
+        .split("||")
+        .map(|row| {
+            row.split('|')
+                .map(str::trim)
+                .filter(|label| !label.is_empty())
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        })
+        .filter(|row| !row.is_empty())
+        .collect();
+    if rows.is_empty() {
+        return Err(anyhow!(
+            "Invalid --reply-keyboard value '{}': expected 'A|B||C|D'",
+            raw
+        ));
+    }
+    Ok(rows)
+}
+
+fn validate_webhook_url(raw: &str) -> Result<()> {
+    let url = url::Url::parse(raw).map_err(|err| anyhow!("not a valid absolute URL ({})", err))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none_or(str::is_empty) {
+        return Err(anyhow!("expected an http(s) URL with a host"));
+    }
+    Ok(())
+}
+
+fn validate_button_url(raw: &str) -> Result<()> {
+    let url = url::Url::parse(raw).map_err(|err| anyhow!("not a valid absolute URL ({})", err))?;
+    match url.scheme() {
+        "http" | "https" => {
+            if url.host_str().is_none_or(str::is_empty) {
+                return Err(anyhow!("URL is missing a host"));
+            }
+        }
+        // tg://resolve?domain=..., tg://user?id=... and friends.
+        "tg" => {
+            if url.host_str().is_none_or(str::is_empty) {
+                return Err(anyhow!(
+                    "tg:// links need an action such as tg://resolve?domain=name"
+                ));
+            }
+        }
+        other => {
+            return Err(anyhow!(
+                "unsupported scheme '{}' (use http, https or tg)",
+                other
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn parse_callback_spec(entry: &str) -> Result<ButtonSpec> {
+    let (text, data) = entry
+        .split_once('|')
+        .map(|(text, data)| (text.trim(), data.trim()))
+        .filter(|(text, data)| !text.is_empty() && !data.is_empty())
+        .ok_or_else(|| {
+            anyhow!(
+                "Invalid --callback value '{}': expected 'Label|data'",
+                entry
+            )
+        })?;
+    if data.len() > 64 {
+        return Err(anyhow!(
+            "Invalid --callback value '{}': callback data must be at most 64 bytes",
+            entry
+        ));
+    }
+    Ok(ButtonSpec::Callback {
+        text: text.to_string(),
+        data: data.to_string(),
+    })
+}
+
+fn parse_button_specs(raw: &[String]) -> Result<Vec<ButtonSpec>> {
+    let mut specs = Vec::new();
+
+    for entry in raw {
+        if entry == BUTTON_ROW_BREAK {
+            specs.push(ButtonSpec::RowBreak);
+            continue;
+        }
+
+        let mut parts = entry.splitn(2, '|');
+        let text = parts
+            .next()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow!("Invalid --button value '{}': missing label", entry))?;
+        let url = parts
+            .next()
+            .map(str::trim)
             .filter(|s| !s.is_empty())
             .ok_or_else(|| anyhow!("Invalid --button value '{}': expected 'Label|URL'", entry))?;
 
+        validate_button_url(url)
+            .map_err(|err| anyhow!("Invalid --button value '{}': {}", entry, err))?;
+
         specs.push(ButtonSpec::Link {
             text: text.to_string(),
             url: url.to_string(),
@@ -268,3 +2474,73 @@ fn parse_button_specs(raw: &[String]) -> Result<Vec<ButtonSpec>> {
 
     Ok(specs)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn callback_spec_splits_label_and_data() {
+        match parse_callback_spec(" Yes | vote:yes ").unwrap() {
+            ButtonSpec::Callback { text, data } => {
+                assert_eq!(text, "Yes");
+                assert_eq!(data, "vote:yes");
+            }
+            _ => panic!("expected a callback button"),
+        }
+    }
+
+    #[test]
+    fn callback_spec_rejects_missing_parts() {
+        assert!(parse_callback_spec("Yes").is_err());
+        assert!(parse_callback_spec("Yes|").is_err());
+        assert!(parse_callback_spec("|data").is_err());
+    }
+
+    #[test]
+    fn callback_spec_limits_data_to_64_bytes() {
+        assert!(parse_callback_spec(&format!("Ok|{}", "x".repeat(64))).is_ok());
+        let err = parse_callback_spec(&format!("Ok|{}", "x".repeat(65))).unwrap_err();
+        assert!(err.to_string().contains("at most 64 bytes"));
+    }
+
+    #[test]
+    fn reply_keyboard_splits_rows_and_labels() {
+        assert_eq!(
+            parse_reply_keyboard(" A | B || C |").unwrap(),
+            vec![vec!["A", "B"], vec!["C"]]
+        );
+    }
+
+    #[test]
+    fn reply_keyboard_rejects_empty_layout() {
+        assert!(parse_reply_keyboard("|| |").is_err());
+    }
+
+    #[test]
+    fn button_url_accepts_http_https_and_tg() {
+        assert!(validate_button_url("https://example.com/path").is_ok());
+        assert!(validate_button_url("http://example.com").is_ok());
+        assert!(validate_button_url("tg://resolve?domain=name").is_ok());
+    }
+
+    #[test]
+    fn button_url_rejects_other_schemes_and_relative_urls() {
+        assert!(validate_button_url("ftp://example.com").is_err());
+        assert!(validate_button_url("javascript:alert(1)").is_err());
+        assert!(validate_button_url("example.com").is_err());
+    }
+
+    #[test]
+    fn mention_uses_escaped_label_or_default() {
+        assert_eq!(
+            parse_mention("42:Ops & <Dev>").unwrap(),
+            "<a href=\"tg://user?id=42\">Ops &amp; &lt;Dev&gt;</a>"
+        );
+        assert_eq!(
+            parse_mention("42").unwrap(),
+            "<a href=\"tg://user?id=42\">@user</a>"
+        );
+        assert!(parse_mention("alice:Alice").is_err());
+    }
+}
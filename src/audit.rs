@@ -0,0 +1,56 @@
+use crate::log_warn;
+use once_cell::sync::OnceCell;
+use serde_json::json;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const AUDIT_FILE: &str = "audit.log";
+
+static AUDIT_LOG: OnceCell<PathBuf> = OnceCell::new();
+
+pub fn default_audit_path() -> anyhow::Result<PathBuf> {
+    Ok(crate::config::config_file_path()?.with_file_name(AUDIT_FILE))
+}
+
+/// Turns on auditing for the rest of the process. Nothing is recorded until
+/// this is called.
+pub fn init(path: &Path) {
+    let _ = AUDIT_LOG.set(path.to_path_buf());
+}
+
+/// Appends one JSON line per send; `bot_token` is masked in error messages.
+pub fn record<T>(action: &str, chat_id: &str, result: &anyhow::Result<T>, bot_token: &str) {
+    let Some(path) = AUDIT_LOG.get() else {
+        return;
+    };
+
+    let mut entry = json!({
+        "timestamp": chrono::Local::now().to_rfc3339(),
+        "action": action,
+        "chat_id": chat_id,
+        "result": if result.is_ok() { "ok" } else { "err" },
+    });
+    if let Err(err) = result {
+        let message = format!("{:#}", err);
+        entry["error"] = json!(if bot_token.is_empty() {
+            message
+        } else {
+            message.replace(bot_token, &crate::utils::redact_token(bot_token))
+        });
+    }
+
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", entry));
+    if let Err(err) = written {
+        log_warn!("Failed to write audit log {}: {}", path.display(), err);
+    }
+}
@@ -0,0 +1,165 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::path::{Path, PathBuf};
+
+/// One send from a batch file. Missing fields keep the values given on the
+/// command line (or in the config file, for `chat_id`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchEntry {
+    #[serde(
+        default,
+        deserialize_with = "string_or_number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub chat_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub media: Vec<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub silent: Option<bool>,
+}
+
+impl BatchEntry {
+    pub fn has_content(&self) -> bool {
+        self.message.is_some() || !self.media.is_empty()
+    }
+}
+
+/// Reads `chat_id,message,media_path,caption` rows; the header row decides
+/// which columns are present and in what order.
+pub fn read_csv(path: &Path) -> Result<Vec<BatchEntry>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|err| anyhow!("Failed to read batch file {}: {}", path.display(), err))?;
+
+    let headers = reader
+        .headers()
+        .map_err(|err| anyhow!("Failed to read headers of {}: {}", path.display(), err))?
+        .clone();
+    for header in headers.iter() {
+        if !matches!(
+            header,
+            "chat_id" | "message" | "media_path" | "media" | "caption"
+        ) {
+            return Err(anyhow!(
+                "Unknown column '{}' in {} (expected chat_id, message, media_path, caption)",
+                header,
+                path.display()
+            ));
+        }
+    }
+
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut entries = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let row = index + 2;
+        let record = record.map_err(|err| anyhow!("{} row {}: {}", path.display(), row, err))?;
+        let mut entry = BatchEntry::default();
+        for (header, value) in headers.iter().zip(record.iter()) {
+            if value.is_empty() {
+                continue;
+            }
+            match header {
+                "chat_id" => entry.chat_id = Some(value.to_string()),
+                "message" => entry.message = Some(value.to_string()),
+                "media_path" | "media" => entry.media.push(resolve(base, value)),
+                "caption" => entry.caption = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        if !entry.has_content() {
+            return Err(anyhow!(
+                "{} row {}: needs a message or a media_path",
+                path.display(),
+                row
+            ));
+        }
+        entries.push(entry);
+    }
+
+    if entries.is_empty() {
+        return Err(anyhow!("Batch file {} has no rows", path.display()));
+    }
+    Ok(entries)
+}
+
+/// Reads a JSON array of objects with `chat_id`, `message`, `media` (a path or
+/// a list of paths), `caption` and `silent` keys.
+pub fn read_json(path: &Path) -> Result<Vec<BatchEntry>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| anyhow!("Failed to read batch file {}: {}", path.display(), err))?;
+    let mut entries: Vec<BatchEntry> = serde_json::from_str(&content)
+        .map_err(|err| anyhow!("Invalid batch file {}: {}", path.display(), err))?;
+
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    for (index, entry) in entries.iter_mut().enumerate() {
+        if !entry.has_content() {
+            return Err(anyhow!(
+                "{} entry {}: needs a message or media",
+                path.display(),
+                index + 1
+            ));
+        }
+        for media in entry.media.iter_mut() {
+            *media = resolve(base, &media.to_string_lossy());
+        }
+    }
+
+    if entries.is_empty() {
+        return Err(anyhow!("Batch file {} has no entries", path.display()));
+    }
+    Ok(entries)
+}
+
+fn string_or_number<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ChatId {
+        Text(String),
+        Number(i64),
+    }
+    Ok(
+        Option::<ChatId>::deserialize(deserializer)?.map(|id| match id {
+            ChatId::Text(text) => text,
+            ChatId::Number(number) => number.to_string(),
+        }),
+    )
+}
+
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<PathBuf>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Media {
+        One(PathBuf),
+        Many(Vec<PathBuf>),
+    }
+    Ok(match Option::<Media>::deserialize(deserializer)? {
+        Some(Media::One(path)) => vec![path],
+        Some(Media::Many(paths)) => paths,
+        None => Vec::new(),
+    })
+}
+
+fn resolve(base: &Path, value: &str) -> PathBuf {
+    let path = PathBuf::from(value);
+    if path.is_absolute() {
+        path
+    } else {
+        base.join(path)
+    }
+}
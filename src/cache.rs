@@ -0,0 +1,79 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+pub const CACHE_FILE: &str = "file_cache.json";
+
+/// One line of the cache file. `file_id`s are only valid for the bot that
+/// uploaded the file, so entries are keyed by bot ID as well as content.
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    hash: String,
+    media_type: String,
+    bot_id: String,
+    file_id: String,
+}
+
+pub fn cache_file_path() -> Result<PathBuf> {
+    Ok(crate::config::config_file_path()?.with_file_name(CACHE_FILE))
+}
+
+pub fn hash_file(path: &Path) -> Result<String> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut file, &mut hasher)
+        .with_context(|| format!("Failed to hash {}", path.display()))?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+pub fn lookup(hash: &str, media_type: &str, bot_id: &str) -> Option<String> {
+    let file = File::open(cache_file_path().ok()?).ok()?;
+    // Later lines win, so a re-upload replaces an older file_id.
+    BufReader::new(file)
+        .lines()
+        .map_while(|line| line.ok())
+        .filter_map(|line| serde_json::from_str::<CacheEntry>(&line).ok())
+        .filter(|entry| {
+            entry.hash == hash && entry.media_type == media_type && entry.bot_id == bot_id
+        })
+        .last()
+        .map(|entry| entry.file_id)
+}
+
+pub fn store(hash: &str, media_type: &str, bot_id: &str, file_id: &str) -> Result<()> {
+    let path = cache_file_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let entry = CacheEntry {
+        hash: hash.to_string(),
+        media_type: media_type.to_string(),
+        bot_id: bot_id.to_string(),
+        file_id: file_id.to_string(),
+    };
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// The `file_id` Telegram assigned to the uploaded media. Photos come back as
+/// a list of sizes; the last one is the original.
+pub fn file_id_from_response(body: &str, media_type: &str) -> Option<String> {
+    let value: Value = serde_json::from_str(body).ok()?;
+    let media = &value["result"][media_type];
+    let media = match media {
+        Value::Array(sizes) => sizes.last()?,
+        other => other,
+    };
+    media["file_id"].as_str().map(ToString::to_string)
+}
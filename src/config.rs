@@ -1,6 +1,7 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 #[allow(dead_code)]
 pub const URL: &str = "https://github.com/najahiiii/telebot-send";
@@ -18,11 +19,39 @@ pub const VERSION_SUMMARY: &str = concat!(
     env!("SENDTG_CLI_BUILD_TIME")
 );
 
+#[cfg(not(target_os = "windows"))]
 pub const CONFIG_DIR: &str = ".config/sendtg";
 pub const CONFIG_FILE: &str = "config.toml";
+pub const DEFAULT_CONFIG_MAX_AGE_DAYS: u64 = 90;
+/// Layout version written to `config.toml`; bump it together with a step in
+/// `migrate_config` when the format changes.
+pub const CONFIG_VERSION: u32 = 1;
+/// Top-level fields that `--set` and `--unset` can change.
+pub const CONFIG_FIELDS: [&str; 3] = ["api_url", "bot_token", "chat_id"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct FileConfig {
+    pub version: Option<u32>,
+    pub api_url: Option<String>,
+    pub bot_token: Option<String>,
+    pub chat_id: Option<String>,
+    pub ffmpeg_path: Option<String>,
+    pub ffprobe_path: Option<String>,
+    pub large_file_mode: Option<bool>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ProfileConfig>,
+}
+
+/// A named send target under `[profiles.<name>]`. Unset fields fall back to
+/// the top-level values.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ProfileConfig {
     pub api_url: Option<String>,
     pub bot_token: Option<String>,
     pub chat_id: Option<String>,
@@ -45,16 +74,68 @@ impl FileConfig {
                 .map(|v| !v.trim().is_empty())
                 .unwrap_or(false)
     }
+
+    /// Returns the top-level field named `name`, as used by `--set` and `--unset`.
+    pub fn field_mut(&mut self, name: &str) -> Result<&mut Option<String>> {
+        match name {
+            "api_url" => Ok(&mut self.api_url),
+            "bot_token" => Ok(&mut self.bot_token),
+            "chat_id" => Ok(&mut self.chat_id),
+            _ => Err(anyhow!(
+                "Unknown config field '{}'; valid fields are: {}",
+                name,
+                CONFIG_FIELDS.join(", ")
+            )),
+        }
+    }
 }
 
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
 pub fn config_file_path() -> Result<PathBuf> {
     let home = std::env::var("HOME").context("$HOME environment variable is not set")?;
     Ok(PathBuf::from(home).join(CONFIG_DIR).join(CONFIG_FILE))
 }
 
+#[cfg(target_os = "macos")]
+pub fn config_file_path() -> Result<PathBuf> {
+    let home = std::env::var("HOME").context("$HOME environment variable is not set")?;
+    Ok(PathBuf::from(home)
+        .join("Library/Application Support/sendtg")
+        .join(CONFIG_FILE))
+}
+
+/// Points macOS users who still have a config in the old XDG-style location at the new one.
+#[cfg(target_os = "macos")]
+fn warn_about_legacy_config(path: &std::path::Path) {
+    let Ok(home) = std::env::var("HOME") else {
+        return;
+    };
+    let legacy = PathBuf::from(home).join(CONFIG_DIR).join(CONFIG_FILE);
+    if legacy.exists() {
+        crate::log_warn!(
+            "Found a config at {}; sendtg now reads {} on macOS. Move it with: mkdir -p \"{}\" && mv \"{}\" \"{}\"",
+            legacy.display(),
+            path.display(),
+            path.parent()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default(),
+            legacy.display(),
+            path.display()
+        );
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn config_file_path() -> Result<PathBuf> {
+    let appdata = std::env::var("APPDATA").context("%APPDATA% environment variable is not set")?;
+    Ok(PathBuf::from(appdata).join("sendtg").join(CONFIG_FILE))
+}
+
 pub fn load_config() -> Result<Option<FileConfig>> {
     let path = config_file_path()?;
     if !path.exists() {
+        #[cfg(target_os = "macos")]
+        warn_about_legacy_config(&path);
         return Ok(None);
     }
 
@@ -62,26 +143,78 @@ pub fn load_config() -> Result<Option<FileConfig>> {
         .with_context(|| format!("Failed to read {}", path.display()))?;
     let config: FileConfig = toml::from_str(&content)
         .with_context(|| format!("Failed to parse TOML from {}", path.display()))?;
+
+    let version = config.version.unwrap_or(0);
+    if version > CONFIG_VERSION {
+        crate::log_warn!(
+            "{} was written by a newer sendtg (config version {}, this build supports {}); some settings may be ignored",
+            path.display(),
+            version,
+            CONFIG_VERSION
+        );
+        return Ok(Some(config));
+    }
+    if version < CONFIG_VERSION {
+        return Ok(Some(migrate_config(config, version)));
+    }
     Ok(Some(config))
 }
 
+/// Upgrades a config read from an older layout, one version at a time. The
+/// result is kept in memory; the next `write_config` saves it.
+fn migrate_config(mut config: FileConfig, from: u32) -> FileConfig {
+    for version in from..CONFIG_VERSION {
+        match version {
+            // Files from before versioning already use the version 1 layout.
+            0 => {}
+            _ => unreachable!("no migration from config version {}", version),
+        }
+    }
+    config.version = Some(CONFIG_VERSION);
+    config
+}
+
+/// Whole days since the config file was last written, if it exists.
+pub fn config_age_days() -> Option<u64> {
+    let modified = std::fs::metadata(config_file_path().ok()?)
+        .ok()?
+        .modified()
+        .ok()?;
+    let age = std::time::SystemTime::now().duration_since(modified).ok()?;
+    Some(age.as_secs() / 86_400)
+}
+
+/// Warns when the config is older than `max_age_days` (0 turns the check off).
+pub fn warn_if_stale(max_age_days: u64) {
+    if max_age_days == 0 {
+        return;
+    }
+    if let Some(age) = config_age_days().filter(|age| *age > max_age_days) {
+        crate::log_warn!(
+            "Configuration was last updated {} days ago; run `sendtg --setup` to confirm the credentials are still valid",
+            age
+        );
+    }
+}
+
 pub fn write_config(config: &FileConfig) -> Result<PathBuf> {
     let mut to_write = config.clone();
+    to_write.version = Some(CONFIG_VERSION);
 
-    if let Some(api_url) = to_write.api_url.as_ref() {
-        if api_url.trim().is_empty() {
-            to_write.api_url = None;
-        }
+    if let Some(api_url) = to_write.api_url.as_ref()
+        && api_url.trim().is_empty()
+    {
+        to_write.api_url = None;
     }
-    if let Some(bot_token) = to_write.bot_token.as_ref() {
-        if bot_token.trim().is_empty() {
-            to_write.bot_token = None;
-        }
+    if let Some(bot_token) = to_write.bot_token.as_ref()
+        && bot_token.trim().is_empty()
+    {
+        to_write.bot_token = None;
     }
-    if let Some(chat_id) = to_write.chat_id.as_ref() {
-        if chat_id.trim().is_empty() {
-            to_write.chat_id = None;
-        }
+    if let Some(chat_id) = to_write.chat_id.as_ref()
+        && chat_id.trim().is_empty()
+    {
+        to_write.chat_id = None;
     }
 
     let path = config_file_path()?;
@@ -96,3 +229,67 @@ pub fn write_config(config: &FileConfig) -> Result<PathBuf> {
         .with_context(|| format!("Failed to write {}", path.display()))?;
     Ok(path)
 }
+
+/// Picks `config.toml.bak`, or a timestamped name if that already exists.
+fn backup_path(path: &Path) -> PathBuf {
+    let target = path.with_extension("toml.bak");
+    if !target.exists() {
+        return target;
+    }
+    let stamp = chrono::Local::now().format("%Y%m%d%H%M%S");
+    path.with_extension(format!("toml.bak.{}", stamp))
+}
+
+/// Reads a config written as JSON or TOML, using the same keys as config.toml.
+pub fn read_config_file(path: &Path, format: ConfigFormat) -> Result<FileConfig> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    match format {
+        ConfigFormat::Json => serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse JSON from {}", path.display())),
+        ConfigFormat::Toml => toml::from_str(&content)
+            .with_context(|| format!("Failed to parse TOML from {}", path.display())),
+    }
+}
+
+/// Copies the current config next to itself before it gets replaced.
+pub fn backup_config() -> Result<Option<PathBuf>> {
+    let path = config_file_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let target = backup_path(&path);
+    std::fs::copy(&path, &target).with_context(|| {
+        format!(
+            "Failed to back up {} to {}",
+            path.display(),
+            target.display()
+        )
+    })?;
+    Ok(Some(target))
+}
+
+/// Moves the config file aside (to `config.toml.bak`, or a timestamped name if that
+/// exists) and writes an empty one. Returns the backup path when a file was moved.
+pub fn reset_config() -> Result<Option<PathBuf>> {
+    let path = config_file_path()?;
+    let mut backup = None;
+
+    if path.exists() {
+        let target = backup_path(&path);
+        std::fs::rename(&path, &target).with_context(|| {
+            format!(
+                "Failed to back up {} to {}",
+                path.display(),
+                target.display()
+            )
+        })?;
+        backup = Some(target);
+    } else if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+
+    std::fs::write(&path, "").with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(backup)
+}
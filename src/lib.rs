@@ -0,0 +1,40 @@
+//! Send messages and media to Telegram through the Bot API.
+//!
+//! The `sendtg` binary is a thin wrapper around this crate; other programs can
+//! use [`SendTg`] directly:
+//!
+//! ```no_run
+//! use sendtg::SendTg;
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let mut client = SendTg::new(
+//!         "https://api.telegram.org/bot".to_string(),
+//!         "123456:ABCDEF".to_string(),
+//!         "-1001234567890".to_string(),
+//!     )?;
+//!     client.send_message("-1001234567890", "Hello from Rust", false, None, None, None)?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! With the `async` feature, `AsyncSendTg` offers the same basics without
+//! blocking the executor.
+
+pub mod args;
+pub mod audit;
+pub mod batch;
+pub mod cache;
+pub mod config;
+pub mod logger;
+pub mod markdown;
+pub mod output;
+pub mod queue;
+pub mod telegram;
+pub mod utils;
+
+pub use crate::args::Args;
+pub use crate::config::FileConfig;
+#[cfg(feature = "async")]
+pub use crate::telegram::AsyncSendTg;
+pub use crate::telegram::{MediaItem, SendTg};
+pub use crate::utils::{MediaMetadata, VideoMetadata};
@@ -1,18 +1,131 @@
 use chrono::Local;
-use once_cell::sync::Lazy;
+use clap::ValueEnum;
+use once_cell::sync::{Lazy, OnceCell};
+use serde_json::json;
 use std::fmt;
-use std::sync::Mutex;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, IsTerminal, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 static LOG_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+static LOG_FILE: OnceCell<Arc<Mutex<BufWriter<File>>>> = OnceCell::new();
+static LOG_FORMAT: OnceCell<LogFormat> = OnceCell::new();
+static QUIET: AtomicBool = AtomicBool::new(false);
+static COLOR: AtomicBool = AtomicBool::new(false);
 
-pub(crate) fn log(level: &str, args: fmt::Arguments<'_>) {
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    Always,
+    #[default]
+    Auto,
+    Never,
+}
+
+/// `auto` colors only when stdout is a terminal and `NO_COLOR` is unset.
+pub(crate) fn set_color(choice: ColorChoice) {
+    let enabled = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+        }
+    };
+    COLOR.store(enabled, Ordering::Relaxed);
+}
+
+pub(crate) fn set_log_format(format: LogFormat) {
+    let _ = LOG_FORMAT.set(format);
+}
+
+pub(crate) fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub(crate) fn init_log_file(path: &Path) {
+    match OpenOptions::new().create(true).append(true).open(path) {
+        Ok(file) => {
+            let _ = LOG_FILE.set(Arc::new(Mutex::new(BufWriter::new(file))));
+        }
+        Err(err) => {
+            eprintln!("Failed to open log file {}: {}", path.display(), err);
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn log(level: &str, args: fmt::Arguments<'_>) {
     if let Ok(guard) = LOG_LOCK.lock() {
-        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
-        println!("[{}] - {} - {}", timestamp, level, args);
+        let line = format_line(level, args);
+        if level == "ERROR" || !QUIET.load(Ordering::Relaxed) {
+            println!("{}", colorize(level, &line));
+        }
+        write_to_file(&line);
         drop(guard);
     }
 }
 
+fn format_line(level: &str, args: fmt::Arguments<'_>) -> String {
+    let now = Local::now();
+    match LOG_FORMAT.get().copied().unwrap_or_default() {
+        LogFormat::Text => format!(
+            "[{}] - {} - {}",
+            now.format("%Y-%m-%d %H:%M:%S"),
+            level,
+            args
+        ),
+        LogFormat::Json => json!({
+            "timestamp": now.to_rfc3339(),
+            "level": level,
+            "message": args.to_string(),
+            "target": "sendtg",
+        })
+        .to_string(),
+    }
+}
+
+/// Colors the level name of a text line; JSON lines and the log file stay plain.
+fn colorize(level: &str, line: &str) -> String {
+    if !COLOR.load(Ordering::Relaxed)
+        || LOG_FORMAT.get().copied().unwrap_or_default() != LogFormat::Text
+    {
+        return line.to_string();
+    }
+    let code = match level {
+        "ERROR" => "1;31",
+        "WARN" => "33",
+        "INFO" => "32",
+        "DEBUG" => "34",
+        _ => return line.to_string(),
+    };
+    line.replacen(
+        &format!(" - {} - ", level),
+        &format!(" - \x1b[{}m{}\x1b[0m - ", code, level),
+        1,
+    )
+}
+
+fn write_to_file(line: &str) {
+    let Some(file) = LOG_FILE.get() else {
+        return;
+    };
+
+    if let Ok(mut writer) = file.lock() {
+        // Flush every line since the process may exit without running destructors.
+        if let Err(err) = writeln!(writer, "{}", line).and_then(|_| writer.flush()) {
+            eprintln!("Failed to write to log file: {}", err);
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! log_info {
     ($($arg:tt)*) => {
@@ -20,6 +133,13 @@ macro_rules! log_info {
     };
 }
 
+#[macro_export]
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        $crate::logger::log("WARN", format_args!($($arg)*));
+    };
+}
+
 #[macro_export]
 macro_rules! log_error {
     ($($arg:tt)*) => {
@@ -1,30 +1,357 @@
-mod args;
-mod config;
-mod logger;
-mod telegram;
-mod utils;
-
-use crate::args::{Args, ParsedArgs, SetupArgs};
-use crate::config::FileConfig;
-use crate::telegram::SendTg;
 use anyhow::{Context, Result, anyhow};
+use sendtg::args::{Args, Credentials, ImportArgs, ParsedArgs, QueueFile, SetupArgs};
+use sendtg::batch::BatchEntry;
+use sendtg::config::FileConfig;
+use sendtg::telegram::SendTg;
+use sendtg::{log_error, log_info, log_warn};
 use std::io::{self, Write};
 use std::process;
 
 fn run() -> Result<()> {
     match Args::parse()? {
         ParsedArgs::Setup(setup_args) => handle_setup(setup_args),
-        ParsedArgs::ShowConfig => handle_show_config(),
+        ParsedArgs::ValidateConfig => handle_validate_config(),
+        ParsedArgs::ResetConfig { yes } => handle_reset_config(yes),
+        ParsedArgs::ImportConfig(import_args) => handle_import_config(&import_args),
+        ParsedArgs::SetField { field, value } => handle_set_field(&field, value),
+        ParsedArgs::UnsetField { field } => handle_unset_field(&field),
+        ParsedArgs::ShowConfig { export, redact } => {
+            if export {
+                handle_export_config(redact)
+            } else {
+                handle_show_config()
+            }
+        }
+        ParsedArgs::Completions(shell) => sendtg::args::write_completions(shell),
+        ParsedArgs::ManPage => sendtg::args::write_man_page(),
+        ParsedArgs::Run(args) if args.queue.is_some() => enqueue(&args),
+        ParsedArgs::FlushQueue(queue_file) => {
+            // Read before flushing so the report names the chats of the sent entries.
+            let mut report_args = queue_file.args.clone();
+            report_args.batch = sendtg::queue::read(&queue_file.path).unwrap_or_default();
+            let result = flush_queue(&queue_file);
+            notify_webhook(&report_args, &result);
+            result
+        }
         ParsedArgs::Run(args) => {
-            let mut client = SendTg::new(
-                args.api_url.clone(),
-                args.bot_token.clone(),
-                args.chat_id.clone(),
-            )?;
-            client.run(&args)?;
-            Ok(())
+            let result = if !args.batch.is_empty() {
+                run_batch(&args)
+            } else if args.broadcast.is_empty() {
+                SendTg::new(
+                    args.api_url.clone(),
+                    args.bot_token.clone(),
+                    args.chat_id.clone(),
+                )
+                .and_then(|mut client| client.run(&args))
+            } else {
+                run_broadcast(&args)
+            };
+            run_hooks(&args, &result);
+            notify_webhook(&args, &result);
+            result
+        }
+        ParsedArgs::Poll(poll_args) => client_for(&poll_args.credentials)?.send_poll(&poll_args),
+        ParsedArgs::Forward(forward_args) => {
+            client_for(&forward_args.credentials)?.forward_message(&forward_args)
+        }
+        ParsedArgs::Copy(copy_args) => client_for(&copy_args.credentials)?.copy_message(&copy_args),
+        ParsedArgs::Delete(delete_args) => {
+            client_for(&delete_args.credentials)?.delete_messages(&delete_args)
+        }
+        ParsedArgs::Edit(edit_args) => client_for(&edit_args.credentials)?.edit_message(&edit_args),
+        ParsedArgs::GetChat(credentials) => client_for(&credentials)?.get_chat_info(),
+        ParsedArgs::ExportInviteLink(credentials) => client_for(&credentials)?.export_invite_link(),
+        ParsedArgs::CheckToken(credentials) => {
+            SendTg::without_chat(credentials.api_url, credentials.bot_token)?.check_token()
+        }
+    }
+}
+
+fn run_batch(args: &Args) -> Result<()> {
+    let total = args.batch.len();
+    let mut failed = 0;
+    for (index, entry) in args.batch.iter().enumerate() {
+        if index > 0
+            && let Some(delay) = args.delay_secs.filter(|delay| *delay > 0)
+        {
+            std::thread::sleep(std::time::Duration::from_secs(delay));
         }
+
+        let entry_args = args_for_entry(args, entry);
+        log_info!(
+            "Batch entry {} of {} ({})",
+            index + 1,
+            total,
+            entry_args.chat_id
+        );
+        if let Err(err) = send_entry(&entry_args) {
+            if !args.continue_on_error {
+                return Err(anyhow!("Batch entry {} failed: {:#}", index + 1, err));
+            }
+            log_error!("Batch entry {} failed: {:#}", index + 1, err);
+            failed += 1;
+        }
+    }
+
+    log_info!(
+        "Batch finished: {} of {} entries sent",
+        total - failed,
+        total
+    );
+    if failed > 0 {
+        return Err(anyhow!("{} of {} batch entries failed", failed, total));
+    }
+    Ok(())
+}
+
+/// Command-line values act as defaults for anything the entry leaves out.
+fn args_for_entry(args: &Args, entry: &BatchEntry) -> Args {
+    let mut entry_args = args.clone();
+    entry_args.batch = Vec::new();
+    if let Some(chat_id) = entry.chat_id.as_ref() {
+        entry_args.chat_id = chat_id.clone();
     }
+    if let Some(message) = entry.message.as_ref() {
+        entry_args.message = Some(message.clone());
+    }
+    if !entry.media.is_empty() {
+        entry_args.media_paths = entry.media.clone();
+    }
+    if let Some(caption) = entry.caption.as_ref() {
+        entry_args.captions = vec![caption.clone()];
+    }
+    if let Some(silent) = entry.silent {
+        entry_args.silent = silent;
+    }
+    entry_args
+}
+
+fn send_entry(args: &Args) -> Result<()> {
+    SendTg::new(
+        args.api_url.clone(),
+        args.bot_token.clone(),
+        args.chat_id.clone(),
+    )
+    .and_then(|mut client| client.run(args))
+}
+
+fn enqueue(args: &Args) -> Result<()> {
+    let Some(path) = args.queue.as_deref() else {
+        return Ok(());
+    };
+    if args
+        .media_paths
+        .iter()
+        .any(|media| media.as_os_str() == "-")
+    {
+        return Err(anyhow!("--media - cannot be queued"));
+    }
+    if args.captions.len() > 1 {
+        return Err(anyhow!("Only one caption can be queued per entry"));
+    }
+    // Media is stored as absolute paths since the queue may be flushed from
+    // another directory.
+    let media = args
+        .media_paths
+        .iter()
+        .map(|media| {
+            std::path::absolute(media)
+                .with_context(|| format!("Failed to resolve {}", media.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let entry = BatchEntry {
+        chat_id: Some(args.chat_id.clone()),
+        message: args.message.clone(),
+        media,
+        caption: args.captions.first().cloned(),
+        silent: args.silent.then_some(true),
+    };
+    if !entry.has_content() {
+        return Err(anyhow!("--queue needs a message or media to queue"));
+    }
+    sendtg::queue::append(path, &entry)?;
+    log_info!("Queued send to {} in {}", args.chat_id, path.display());
+    Ok(())
+}
+
+fn flush_queue(queue_file: &QueueFile) -> Result<()> {
+    let path = queue_file.path.as_path();
+    let args = &queue_file.args;
+    let pending = sendtg::queue::read(path)?;
+    let total = pending.len();
+    if total == 0 {
+        log_info!("Queue {} is empty", path.display());
+        return Ok(());
+    }
+
+    let mut attempted = 0;
+    let mut failed = 0;
+    for entry in &pending {
+        if attempted > 0
+            && let Some(delay) = args.delay_secs.filter(|delay| *delay > 0)
+        {
+            std::thread::sleep(std::time::Duration::from_secs(delay));
+        }
+        attempted += 1;
+
+        let entry_args = args_for_entry(args, entry);
+        log_info!(
+            "Queue entry {} of {} ({})",
+            attempted,
+            total,
+            entry_args.chat_id
+        );
+        match send_entry(&entry_args) {
+            // A dry run sends nothing, so the entry stays queued.
+            Ok(()) if args.dry_run => {}
+            Ok(()) => sendtg::queue::remove(path, entry)?,
+            Err(err) => {
+                if !args.continue_on_error {
+                    return Err(anyhow!("Queue entry {} failed: {:#}", attempted, err));
+                }
+                log_error!("Queue entry {} failed: {:#}", attempted, err);
+                failed += 1;
+            }
+        }
+    }
+
+    log_info!(
+        "Queue flushed: {} of {} entries sent",
+        total - failed,
+        total
+    );
+    if failed > 0 {
+        return Err(anyhow!(
+            "{} of {} queue entries failed and remain in {}",
+            failed,
+            total,
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+fn run_broadcast(args: &Args) -> Result<()> {
+    let mut failed = Vec::new();
+    for (name, credentials) in &args.broadcast {
+        log_info!(
+            "Broadcasting to profile '{}' ({})",
+            name,
+            credentials.chat_id
+        );
+        let mut profile_args = args.clone();
+        profile_args.api_url = credentials.api_url.clone();
+        profile_args.bot_token = credentials.bot_token.clone();
+        profile_args.chat_id = credentials.chat_id.clone();
+        let result = client_for(credentials).and_then(|mut client| client.run(&profile_args));
+        if let Err(err) = result {
+            log_error!("Profile '{}' failed: {:#}", name, err);
+            failed.push(name.as_str());
+        }
+    }
+
+    let total = args.broadcast.len();
+    log_info!(
+        "Broadcast finished: {} of {} profiles succeeded",
+        total - failed.len(),
+        total
+    );
+    if !failed.is_empty() {
+        return Err(anyhow!("Broadcast failed for: {}", failed.join(", ")));
+    }
+    Ok(())
+}
+
+/// The error text handed to hooks and webhooks, with the bot token masked.
+fn redacted_error(args: &Args, err: &anyhow::Error) -> String {
+    format!("{:#}", err).replace(
+        &args.bot_token,
+        &sendtg::utils::redact_token(&args.bot_token),
+    )
+}
+
+fn run_hooks(args: &Args, result: &Result<()>) {
+    let (hook, error) = match result {
+        Ok(()) => (args.on_success.as_deref(), None),
+        Err(err) => (args.on_failure.as_deref(), Some(redacted_error(args, err))),
+    };
+    let Some(hook) = hook else {
+        return;
+    };
+
+    let mut command = sendtg::utils::shell_command(hook);
+    if let Some(error) = error {
+        command.env("SENDTG_ERROR", error);
+    }
+    match command.status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => {
+            log_warn!("Hook '{}' exited with {}", hook, status);
+        }
+        Err(err) => {
+            log_warn!("Failed to run hook '{}': {}", hook, err);
+        }
+    }
+}
+
+fn notify_webhook(args: &Args, result: &Result<()>) {
+    let Some(url) = args.webhook_notify.as_deref() else {
+        return;
+    };
+    let chat_id = report_chat_id(args);
+    let payload = match result {
+        Ok(()) => serde_json::json!({
+            "status": "ok",
+            "chat_id": chat_id,
+            "files_sent": sendtg::telegram::files_sent(),
+        }),
+        Err(err) => serde_json::json!({
+            "status": "error",
+            "chat_id": chat_id,
+            "files_sent": sendtg::telegram::files_sent(),
+            "error": redacted_error(args, err),
+        }),
+    };
+    if args.dry_run {
+        log_info!("Dry run: would POST {} to webhook {}", payload, url);
+        return;
+    }
+    if let Err(err) = sendtg::utils::post_webhook(url, &payload) {
+        log_warn!("Webhook notification failed: {:#}", err);
+    }
+}
+
+/// The chat the report is about; batches and broadcasts list every chat they
+/// sent to.
+fn report_chat_id(args: &Args) -> serde_json::Value {
+    let chat_ids: Vec<&str> = if !args.batch.is_empty() {
+        args.batch
+            .iter()
+            .map(|entry| entry.chat_id.as_deref().unwrap_or(&args.chat_id))
+            .collect()
+    } else if !args.broadcast.is_empty() {
+        args.broadcast
+            .iter()
+            .map(|(_, credentials)| credentials.chat_id.as_str())
+            .collect()
+    } else {
+        return serde_json::json!(args.chat_id);
+    };
+    let mut unique: Vec<&str> = Vec::new();
+    for chat_id in chat_ids {
+        if !unique.contains(&chat_id) {
+            unique.push(chat_id);
+        }
+    }
+    serde_json::json!(unique)
+}
+
+fn client_for(credentials: &Credentials) -> Result<SendTg> {
+    SendTg::new(
+        credentials.api_url.clone(),
+        credentials.bot_token.clone(),
+        credentials.chat_id.clone(),
+    )
 }
 
 fn prompt_input(prompt: &str) -> Result<String> {
@@ -80,12 +407,22 @@ fn ensure_value(target: &mut Option<String>, provided: Option<String>, label: &s
 }
 
 fn handle_setup(setup_args: SetupArgs) -> Result<()> {
-    let mut existing: FileConfig = crate::config::load_config()?.unwrap_or_default();
+    let mut existing: FileConfig = sendtg::config::load_config()?.unwrap_or_default();
 
     existing.api_url = normalize_option(existing.api_url);
     existing.bot_token = normalize_option(existing.bot_token);
     existing.chat_id = normalize_option(existing.chat_id);
 
+    if setup_args.config_max_age > 0
+        && let Some(age) =
+            sendtg::config::config_age_days().filter(|age| *age > setup_args.config_max_age)
+    {
+        log_warn!(
+            "Existing configuration is {} days old; check that the saved credentials are still valid",
+            age
+        );
+    }
+
     ensure_value(&mut existing.api_url, setup_args.api_url.clone(), "API URL")?;
     ensure_value(
         &mut existing.bot_token,
@@ -104,28 +441,67 @@ fn handle_setup(setup_args: SetupArgs) -> Result<()> {
         return Err(anyhow!("Chat ID is required for setup"));
     }
 
-    let path = crate::config::write_config(&existing)?;
+    let path = sendtg::config::write_config(&existing)?;
     log_info!("Configuration saved to {}", path.display());
     Ok(())
 }
 
 fn handle_show_config() -> Result<()> {
-    let path = crate::config::config_file_path()?;
+    let path = sendtg::config::config_file_path()?;
+    let format = sendtg::output::format();
+    if !format.is_text() {
+        let cfg = sendtg::config::load_config()?.unwrap_or_default();
+        let profiles: Vec<&str> = cfg.profiles.keys().map(String::as_str).collect();
+        format.print_record(&[
+            ("config_file", serde_json::json!(path.display().to_string())),
+            ("api_url", serde_json::json!(cfg.api_url)),
+            (
+                "bot_token",
+                serde_json::json!(cfg.bot_token.as_deref().map(sendtg::utils::redact_token)),
+            ),
+            ("chat_id", serde_json::json!(cfg.chat_id)),
+            ("ffmpeg_path", serde_json::json!(cfg.ffmpeg_path)),
+            ("ffprobe_path", serde_json::json!(cfg.ffprobe_path)),
+            ("large_file_mode", serde_json::json!(cfg.large_file_mode)),
+            ("profiles", serde_json::json!(profiles)),
+            (
+                "age_days",
+                serde_json::json!(sendtg::config::config_age_days()),
+            ),
+        ]);
+        return Ok(());
+    }
     println!("Configuration file: {}", path.display());
 
-    match crate::config::load_config()? {
+    match sendtg::config::load_config()? {
         Some(cfg) => {
             let api_url = cfg.api_url.as_deref().unwrap_or("<not set>");
             let bot_token = cfg
                 .bot_token
                 .as_ref()
-                .map(|token| crate::utils::redact_token(token))
+                .map(|token| sendtg::utils::redact_token(token))
                 .unwrap_or_else(|| "<not set>".to_string());
             let chat_id = cfg.chat_id.as_deref().unwrap_or("<not set>");
 
             println!("API URL   : {}", api_url);
             println!("Bot Token : {}", bot_token);
             println!("Chat ID   : {}", chat_id);
+            if let Some(ffmpeg) = cfg.ffmpeg_path.as_deref() {
+                println!("ffmpeg    : {}", ffmpeg);
+            }
+            if let Some(ffprobe) = cfg.ffprobe_path.as_deref() {
+                println!("ffprobe   : {}", ffprobe);
+            }
+            if let Some(large_file) = cfg.large_file_mode {
+                println!("Large file: {}", large_file);
+            }
+            if !cfg.profiles.is_empty() {
+                let names: Vec<&str> = cfg.profiles.keys().map(String::as_str).collect();
+                println!("Profiles  : {}", names.join(", "));
+            }
+            if let Some(age) = sendtg::config::config_age_days() {
+                println!("Age       : {} day(s)", age);
+            }
         }
         None => {
             println!("No configuration found. Run `sendtg --setup` to create one.");
@@ -135,9 +511,225 @@ fn handle_show_config() -> Result<()> {
     Ok(())
 }
 
+fn handle_import_config(import_args: &ImportArgs) -> Result<()> {
+    let imported = sendtg::config::read_config_file(&import_args.path, import_args.format)?;
+    if !imported.has_required_fields() {
+        log_warn!(
+            "{} does not set api_url, bot_token and chat_id; run `sendtg --setup` to fill them in",
+            import_args.path.display()
+        );
+    }
+
+    if let Some(backup) = sendtg::config::backup_config()? {
+        log_info!("Previous configuration saved to {}", backup.display());
+    }
+    let path = sendtg::config::write_config(&imported)?;
+    log_info!(
+        "Imported {} into {}",
+        import_args.path.display(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn handle_set_field(field: &str, value: String) -> Result<()> {
+    let mut cfg = sendtg::config::load_config()?.unwrap_or_default();
+    let value =
+        normalize_owned(value).ok_or_else(|| anyhow!("Value for {} must not be empty", field))?;
+    *cfg.field_mut(field)? = Some(value);
+
+    let path = sendtg::config::write_config(&cfg)?;
+    log_info!("Updated {} in {}", field, path.display());
+    Ok(())
+}
+
+fn handle_unset_field(field: &str) -> Result<()> {
+    let mut cfg = sendtg::config::load_config()?.unwrap_or_default();
+    *cfg.field_mut(field)? = None;
+
+    let path = sendtg::config::write_config(&cfg)?;
+    log_info!("Cleared {} in {}", field, path.display());
+    if !cfg.has_required_fields() {
+        log_warn!(
+            "{} is required for sending; set it again with `sendtg --set {} VALUE`",
+            field,
+            field
+        );
+    }
+    Ok(())
+}
+
+fn handle_reset_config(yes: bool) -> Result<()> {
+    let path = sendtg::config::config_file_path()?;
+    if !yes {
+        let answer = prompt_input(&format!(
+            "Reset configuration at {}? A backup will be kept. [y/N]: ",
+            path.display()
+        ))?;
+        if !matches!(answer.to_lowercase().as_str(), "y" | "yes") {
+            log_info!("Configuration left unchanged.");
+            return Ok(());
+        }
+    }
+
+    match sendtg::config::reset_config()? {
+        Some(backup) => {
+            log_info!(
+                "Configuration reset; previous file saved to {}",
+                backup.display()
+            );
+        }
+        None => {
+            log_info!("Created empty configuration at {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+fn handle_validate_config() -> Result<()> {
+    let path = sendtg::config::config_file_path()?;
+    println!("Configuration file: {}", path.display());
+
+    let mut failed = 0;
+
+    let cfg = match sendtg::config::load_config() {
+        Ok(Some(cfg)) => {
+            report_check(true, "Config file", "found and parsed".to_string());
+            cfg
+        }
+        Ok(None) => {
+            report_check(
+                false,
+                "Config file",
+                "not found; run `sendtg --setup`".to_string(),
+            );
+            return Err(anyhow!("Configuration is invalid"));
+        }
+        Err(err) => {
+            report_check(false, "Config file", format!("{:#}", err));
+            return Err(anyhow!("Configuration is invalid"));
+        }
+    };
+
+    failed += report_check(
+        cfg.has_required_fields(),
+        "Required fields",
+        if cfg.has_required_fields() {
+            "api_url, bot_token and chat_id are set".to_string()
+        } else {
+            "api_url, bot_token and chat_id must all be set".to_string()
+        },
+    );
+
+    let api_url = cfg.api_url.as_deref().unwrap_or_default();
+    match reqwest::Url::parse(api_url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {
+            failed += report_check(true, "API URL", api_url.to_string())
+        }
+        Ok(url) => {
+            failed += report_check(
+                false,
+                "API URL",
+                format!("unsupported scheme '{}'", url.scheme()),
+            )
+        }
+        Err(err) => {
+            failed += report_check(
+                false,
+                "API URL",
+                format!("'{}' is not a URL: {}", api_url, err),
+            )
+        }
+    }
+
+    let bot_token = cfg.bot_token.as_deref().unwrap_or_default();
+    let token_ok = sendtg::utils::is_valid_token_format(bot_token);
+    failed += report_check(
+        token_ok,
+        "Bot token format",
+        if token_ok {
+            sendtg::utils::redact_token(bot_token)
+        } else {
+            "expected <numeric bot id>:<secret>".to_string()
+        },
+    );
+
+    let chat_id = cfg.chat_id.as_deref().unwrap_or_default().trim();
+    let chat_ok = sendtg::utils::is_valid_chat_id(chat_id);
+    failed += report_check(
+        chat_ok,
+        "Chat ID format",
+        if chat_ok {
+            chat_id.to_string()
+        } else {
+            "expected a numeric ID or an @username".to_string()
+        },
+    );
+
+    if token_ok && failed == 0 {
+        let live = SendTg::without_chat(api_url.to_string(), bot_token.to_string())
+            .and_then(|client| client.check_token());
+        match live {
+            Ok(()) => {
+                failed += report_check(true, "getMe", "token accepted by Telegram".to_string())
+            }
+            Err(err) => failed += report_check(false, "getMe", err.to_string()),
+        }
+    } else {
+        println!("[SKIP] getMe: skipped because of earlier failures");
+    }
+
+    if failed > 0 {
+        return Err(anyhow!("{} configuration check(s) failed", failed));
+    }
+    Ok(())
+}
+
+fn report_check(passed: bool, label: &str, detail: String) -> usize {
+    let status = if passed { "PASS" } else { "FAIL" };
+    println!("[{}] {}: {}", status, label, detail);
+    usize::from(!passed)
+}
+
+fn handle_export_config(redact: bool) -> Result<()> {
+    let cfg = sendtg::config::load_config()?
+        .ok_or_else(|| anyhow!("No configuration found. Run `sendtg --setup` to create one."))?;
+
+    let bot_token = cfg.bot_token.as_ref().map(|token| {
+        if redact {
+            sendtg::utils::redact_token(token)
+        } else {
+            token.clone()
+        }
+    });
+
+    for (name, value) in [
+        ("SENDTG_API_URL", cfg.api_url.as_ref()),
+        ("SENDTG_BOT_TOKEN", bot_token.as_ref()),
+        ("SENDTG_CHAT_ID", cfg.chat_id.as_ref()),
+    ] {
+        if let Some(value) = value {
+            println!("export {}=\"{}\"", name, shell_escape(value));
+        }
+    }
+
+    Ok(())
+}
+
+fn shell_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if matches!(ch, '\\' | '"' | '$' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
 fn main() {
     if let Err(err) = run() {
-        log_error!("{}", err);
+        log_error!("{:#}", err);
         process::exit(1);
     }
 }
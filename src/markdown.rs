@@ -0,0 +1,264 @@
+use crate::log_warn;
+use crate::utils::escape_html;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use std::collections::BTreeSet;
+
+/// Converts CommonMark to the HTML subset Telegram accepts. Headings become
+/// bold lines, lists get bullet or number prefixes and tables are rendered as
+/// aligned monospace text; anything else without an equivalent is kept as
+/// plain text and reported once.
+pub fn markdown_to_html(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES;
+
+    let mut out = String::new();
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let mut table: Option<Vec<Vec<String>>> = None;
+    let mut unsupported = BTreeSet::new();
+    let mut code_closing = "</pre>";
+
+    for event in Parser::new_ext(markdown, options) {
+        if let Some(rows) = table.as_mut() {
+            match event {
+                Event::Start(Tag::TableHead | Tag::TableRow) => rows.push(Vec::new()),
+                Event::Start(Tag::TableCell) => {
+                    if let Some(row) = rows.last_mut() {
+                        row.push(String::new());
+                    }
+                }
+                Event::Text(text) | Event::Code(text) => {
+                    if let Some(cell) = rows.last_mut().and_then(|row| row.last_mut()) {
+                        cell.push_str(&text);
+                    }
+                }
+                Event::End(TagEnd::Table) => {
+                    out.push_str(&render_table(rows));
+                    out.push_str("\n\n");
+                    table = None;
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Paragraph => {}
+                Tag::Heading { .. } | Tag::Strong => out.push_str("<b>"),
+                Tag::Emphasis => out.push_str("<i>"),
+                Tag::Strikethrough => out.push_str("<s>"),
+                Tag::BlockQuote(_) => out.push_str("<blockquote>"),
+                Tag::CodeBlock(CodeBlockKind::Fenced(language)) if !language.is_empty() => {
+                    let language = language.split_whitespace().next().unwrap_or_default();
+                    out.push_str(&format!(
+                        "<pre><code class=\"language-{}\">",
+                        escape_attribute(language)
+                    ));
+                    code_closing = "</code></pre>";
+                }
+                Tag::CodeBlock(_) => {
+                    out.push_str("<pre>");
+                    code_closing = "</pre>";
+                }
+                Tag::List(start) => {
+                    if !lists.is_empty() && !out.ends_with('\n') {
+                        out.push('\n');
+                    }
+                    lists.push(start);
+                }
+                Tag::Item => {
+                    out.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                    match lists.last_mut() {
+                        Some(Some(number)) => {
+                            out.push_str(&format!("{}. ", number));
+                            *number += 1;
+                        }
+                        _ => out.push_str("• "),
+                    }
+                }
+                Tag::Link { dest_url, .. } => {
+                    out.push_str(&format!("<a href=\"{}\">", escape_attribute(&dest_url)));
+                }
+                Tag::Image { dest_url, .. } => {
+                    unsupported.insert("images (sent as links)");
+                    out.push_str(&format!("<a href=\"{}\">", escape_attribute(&dest_url)));
+                }
+                Tag::Table(_) => table = Some(Vec::new()),
+                Tag::HtmlBlock => {
+                    unsupported.insert("raw HTML (sent as text)");
+                }
+                Tag::FootnoteDefinition(label) => {
+                    out.push_str(&format!("[{}]: ", escape_html(&label)));
+                }
+                _ => {
+                    unsupported.insert("other extensions");
+                }
+            },
+            Event::End(tag) => match tag {
+                TagEnd::Paragraph => {
+                    out.push_str(if lists.is_empty() { "\n\n" } else { "\n" });
+                }
+                TagEnd::Heading(_) => out.push_str("</b>\n\n"),
+                TagEnd::Strong => out.push_str("</b>"),
+                TagEnd::Emphasis => out.push_str("</i>"),
+                TagEnd::Strikethrough => out.push_str("</s>"),
+                TagEnd::BlockQuote(_) => {
+                    trim_trailing_newlines(&mut out);
+                    out.push_str("</blockquote>\n\n");
+                }
+                TagEnd::CodeBlock => {
+                    trim_trailing_newlines(&mut out);
+                    out.push_str(code_closing);
+                    out.push_str("\n\n");
+                }
+                TagEnd::List(_) => {
+                    lists.pop();
+                    if lists.is_empty() {
+                        out.push('\n');
+                    }
+                }
+                TagEnd::Item if !out.ends_with('\n') => out.push('\n'),
+                TagEnd::Link | TagEnd::Image => out.push_str("</a>"),
+                _ => {}
+            },
+            Event::Text(text) => out.push_str(&escape_html(&text)),
+            Event::Code(code) => out.push_str(&format!("<code>{}</code>", escape_html(&code))),
+            Event::Html(html) | Event::InlineHtml(html) => {
+                unsupported.insert("raw HTML (sent as text)");
+                out.push_str(&escape_html(&html));
+            }
+            Event::InlineMath(math) | Event::DisplayMath(math) => {
+                unsupported.insert("math");
+                out.push_str(&escape_html(&math));
+            }
+            Event::FootnoteReference(label) => out.push_str(&format!("[{}]", escape_html(&label))),
+            Event::SoftBreak | Event::HardBreak => out.push('\n'),
+            Event::Rule => out.push_str("──────────\n\n"),
+            Event::TaskListMarker(done) => out.push_str(if done { "☑ " } else { "☐ " }),
+        }
+    }
+
+    for feature in unsupported {
+        log_warn!("Markdown {} not supported by Telegram", feature);
+    }
+    out.trim_end().to_string()
+}
+
+/// Pads every column to its widest cell and separates the header with a rule.
+fn render_table(rows: &[Vec<String>]) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut lines = Vec::new();
+    for (index, row) in rows.iter().enumerate() {
+        let cells: Vec<String> = widths
+            .iter()
+            .enumerate()
+            .map(|(column, width)| {
+                let cell = row.get(column).map(String::as_str).unwrap_or_default();
+                format!("{:<width$}", cell, width = *width)
+            })
+            .collect();
+        lines.push(cells.join(" | ").trim_end().to_string());
+        if index == 0 {
+            let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+            lines.push(rule.join("-+-"));
+        }
+    }
+    format!("<pre>{}</pre>", escape_html(&lines.join("\n")))
+}
+
+fn escape_attribute(value: &str) -> String {
+    escape_html(value).replace('"', "&quot;")
+}
+
+fn trim_trailing_newlines(out: &mut String) {
+    while out.ends_with('\n') {
+        out.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_html_in_text() {
+        assert_eq!(markdown_to_html("a < b & \"c\""), "a &lt; b &amp; \"c\"");
+    }
+
+    #[test]
+    fn escapes_link_attributes() {
+        assert_eq!(
+            markdown_to_html("[x](https://e.com/?a=1&b=\"2\")"),
+            "<a href=\"https://e.com/?a=1&amp;b=&quot;2&quot;\">x</a>"
+        );
+    }
+
+    #[test]
+    fn renders_inline_styles() {
+        assert_eq!(
+            markdown_to_html("**b** *i* ~~s~~ `c<d`"),
+            "<b>b</b> <i>i</i> <s>s</s> <code>c&lt;d</code>"
+        );
+    }
+
+    #[test]
+    fn renders_headings_as_bold() {
+        assert_eq!(markdown_to_html("# Title\n\ntext"), "<b>Title</b>\n\ntext");
+    }
+
+    #[test]
+    fn keeps_fenced_code_language() {
+        assert_eq!(
+            markdown_to_html("```rust\nlet a = 1 < 2;\n```"),
+            "<pre><code class=\"language-rust\">let a = 1 &lt; 2;</code></pre>"
+        );
+    }
+
+    #[test]
+    fn renders_fenced_code_without_language_as_pre() {
+        assert_eq!(markdown_to_html("```\nplain\n```"), "<pre>plain</pre>");
+    }
+
+    #[test]
+    fn numbers_and_indents_nested_lists() {
+        let markdown = "1. one\n2. two\n   - nested\n   - more\n3. three";
+        assert_eq!(
+            markdown_to_html(markdown),
+            "1. one\n2. two\n  • nested\n  • more\n3. three"
+        );
+    }
+
+    #[test]
+    fn renders_task_lists() {
+        assert_eq!(
+            markdown_to_html("- [x] done\n- [ ] todo"),
+            "• ☑ done\n• ☐ todo"
+        );
+    }
+
+    #[test]
+    fn renders_tables_as_aligned_text() {
+        let markdown = "| Name | Qty |\n| --- | --- |\n| apple | 1 |\n| kiwi & co | 20 |";
+        assert_eq!(
+            markdown_to_html(markdown),
+            "<pre>Name      | Qty\n----------+----\napple     | 1\nkiwi &amp; co | 20</pre>"
+        );
+    }
+
+    #[test]
+    fn escapes_raw_html() {
+        assert_eq!(markdown_to_html("<div>x</div>"), "&lt;div&gt;x&lt;/div&gt;");
+    }
+}
@@ -0,0 +1,118 @@
+use clap::ValueEnum;
+use once_cell::sync::{Lazy, OnceCell};
+use serde_json::{Map, Value};
+use std::sync::Mutex;
+
+static FORMAT: OnceCell<Formatter> = OnceCell::new();
+// CSV headers are printed again only when the columns change.
+static LAST_CSV_HEADER: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+/// How results such as `--show-config`, `--check` and send confirmations are
+/// printed on stdout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Formatter {
+    #[default]
+    Text,
+    Json,
+    Csv,
+}
+
+pub(crate) fn set_format(format: Formatter) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn format() -> Formatter {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+impl Formatter {
+    pub fn is_text(self) -> bool {
+        self == Formatter::Text
+    }
+
+    /// Prints one record as a JSON object, or as a CSV header and row. Text
+    /// output is left to the caller, which already has its own wording.
+    pub fn print_record(self, fields: &[(&str, Value)]) {
+        match self {
+            Formatter::Text => {}
+            Formatter::Json => {
+                let object: Map<String, Value> = fields
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.clone()))
+                    .collect();
+                println!("{}", Value::Object(object));
+            }
+            Formatter::Csv => {
+                let header = csv_row(fields.iter().map(|(key, _)| key.to_string()));
+                if let Ok(mut last) = LAST_CSV_HEADER.lock()
+                    && last.as_deref() != Some(header.as_str())
+                {
+                    println!("{}", header);
+                    *last = Some(header);
+                }
+                println!(
+                    "{}",
+                    csv_row(fields.iter().map(|(_, value)| csv_value(value)))
+                );
+            }
+        }
+    }
+}
+
+fn csv_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(csv_value).collect::<Vec<_>>().join(";"),
+        other => other.to_string(),
+    }
+}
+
+fn csv_row(cells: impl Iterator<Item = String>) -> String {
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::Any(b'\n'))
+        .from_writer(Vec::new());
+    let cells: Vec<String> = cells.collect();
+    if writer.write_record(&cells).is_err() {
+        return cells.join(",");
+    }
+    let bytes = writer.into_inner().unwrap_or_default();
+    String::from_utf8_lossy(&bytes)
+        .trim_end_matches('\n')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(cells: &[&str]) -> String {
+        csv_row(cells.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn csv_row_leaves_plain_cells_unquoted() {
+        assert_eq!(row(&["a", "b c", "1"]), "a,b c,1");
+    }
+
+    #[test]
+    fn csv_row_quotes_separators_quotes_and_newlines() {
+        assert_eq!(
+            row(&["a,b", "say \"hi\"", "two\nlines"]),
+            "\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\""
+        );
+    }
+
+    #[test]
+    fn csv_row_keeps_trailing_whitespace_in_last_cell() {
+        assert_eq!(row(&["a", "b "]), "a,b ");
+    }
+
+    #[test]
+    fn csv_value_flattens_json() {
+        assert_eq!(csv_value(&Value::Null), "");
+        assert_eq!(csv_value(&serde_json::json!("text")), "text");
+        assert_eq!(csv_value(&serde_json::json!([1, "a"])), "1;a");
+        assert_eq!(csv_value(&serde_json::json!(true)), "true");
+    }
+}
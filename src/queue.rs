@@ -0,0 +1,81 @@
+use crate::batch::BatchEntry;
+use anyhow::{Context, Result, anyhow};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Appends one entry to the queue file, one JSON object per line.
+pub fn append(path: &Path, entry: &BatchEntry) -> Result<()> {
+    let _lock = lock(path)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open queue file {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)
+        .with_context(|| format!("Failed to write queue file {}", path.display()))?;
+    Ok(())
+}
+
+pub fn read(path: &Path) -> Result<Vec<BatchEntry>> {
+    let _lock = lock(path)?;
+    read_entries(path)
+}
+
+/// Drops the first entry equal to `sent`. The file is re-read under the lock
+/// so entries appended while the flush was sending are kept.
+pub fn remove(path: &Path, sent: &BatchEntry) -> Result<()> {
+    let _lock = lock(path)?;
+    let mut entries = read_entries(path)?;
+    if let Some(position) = entries.iter().position(|entry| entry == sent) {
+        entries.remove(position);
+    }
+    write(path, &entries)
+}
+
+/// Holds an exclusive lock on `<queue>.lock` until the returned file is
+/// dropped. A separate file is used because `write` replaces the queue itself.
+fn lock(path: &Path) -> Result<File> {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let lock_path = PathBuf::from(lock_path);
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+    file.lock()
+        .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+    Ok(file)
+}
+
+fn read_entries(path: &Path) -> Result<Vec<BatchEntry>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read queue file {}", path.display()))?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .map_err(|err| anyhow!("{} line {}: {}", path.display(), index + 1, err))
+        })
+        .collect()
+}
+
+/// Replaces the queue with the entries that are still pending. The new
+/// content is written next to the queue first so an interrupted flush never
+/// leaves a half-written file behind.
+fn write(path: &Path, entries: &[BatchEntry]) -> Result<()> {
+    let mut content = String::new();
+    for entry in entries {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, content)
+        .with_context(|| format!("Failed to write {}", temp.display()))?;
+    std::fs::rename(&temp, path)
+        .with_context(|| format!("Failed to update queue file {}", path.display()))
+}
@@ -1,16 +1,56 @@
-use crate::args::{Args, ButtonSpec};
+use crate::args::{Args, ContactSpec, CopyArgs, DeleteArgs, EditArgs, ForwardArgs, PollArgs};
 use crate::utils;
-use crate::{log_debug, log_error, log_info};
+use crate::{log_debug, log_error, log_info, log_warn};
 use anyhow::{Result, anyhow};
 use rand::{Rng, SeedableRng, rngs::StdRng};
+use rayon::prelude::*;
 use reqwest::StatusCode;
 use reqwest::blocking::{Client, multipart};
 use serde::Serialize;
 use serde_json::{Value, json};
-use std::path::PathBuf;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
 const PHOTO_MAX_BYTES: u64 = 10 * 1024 * 1024;
+const CLOUD_API_MAX_BYTES: u64 = 50 * 1024 * 1024;
+const MESSAGE_MAX_CHARS: usize = 4096;
+
+/// Media files sent by every client in this process, for batch and broadcast
+/// reports that span several `SendTg` instances.
+static FILES_SENT: AtomicUsize = AtomicUsize::new(0);
+
+pub fn files_sent() -> usize {
+    FILES_SENT.load(Ordering::Relaxed)
+}
+
+static ACCEPT_INVALID_CERTS: AtomicBool = AtomicBool::new(false);
+
+/// Makes clients created afterwards accept any TLS certificate.
+pub fn set_accept_invalid_certs(accept: bool) {
+    ACCEPT_INVALID_CERTS.store(accept, Ordering::Relaxed);
+}
+
+fn accept_invalid_certs() -> bool {
+    ACCEPT_INVALID_CERTS.load(Ordering::Relaxed)
+}
+
+static TEST_ENVIRONMENT: AtomicBool = AtomicBool::new(false);
+
+/// Routes requests to Telegram's test environment (`/bot<token>/test/<method>`).
+pub fn set_test_environment(enabled: bool) {
+    TEST_ENVIRONMENT.store(enabled, Ordering::Relaxed);
+}
+
+fn method_url(api_url: &str, bot_token: &str, method: &str) -> String {
+    if TEST_ENVIRONMENT.load(Ordering::Relaxed) {
+        format!("{}{}/test/{}", api_url, bot_token, method)
+    } else {
+        format!("{}{}/{}", api_url, bot_token, method)
+    }
+}
 
 pub struct SendTg {
     api_url: String,
@@ -18,19 +58,49 @@ pub struct SendTg {
     pub chat_id: String,
     chat_name: String,
     client: Client,
+    output_json: bool,
+    print_message_ids: bool,
+    dry_run: bool,
+    caption_parse_mode: Option<String>,
+    message_entities: Option<Value>,
+    spoiler_text: bool,
+    blockquote: Option<&'static str>,
+    disable_content_type_detection: bool,
+    // Mutexes rather than Cells so `--parallel` can share the client across threads.
+    last_message_id: Mutex<Option<i64>>,
+    // First message of the current send, which `--pin` pins.
+    first_message_id: Mutex<Option<i64>>,
+    uploaded: Mutex<(u64, usize)>,
+    progress_group: Option<utils::GroupProgress>,
+    upload_chunk_size: Option<usize>,
+    file_cache: bool,
 }
 
 impl SendTg {
     pub fn new(api_url: String, bot_token: String, chat_id: String) -> Result<Self> {
-        if bot_token.trim().is_empty() {
-            log_error!("Bot token is required!");
-            return Err(anyhow!("Bot token is missing!"));
-        }
-
         if chat_id.trim().is_empty() {
             log_error!("Chat ID is required!");
             return Err(anyhow!("Chat ID is missing!"));
         }
+        let chat_id = chat_id.trim().to_string();
+        if !utils::is_valid_chat_id(&chat_id) {
+            log_error!("Invalid chat ID: {}", chat_id);
+            return Err(anyhow!(
+                "Chat ID must be a numeric ID (e.g. -1001234567890) or an @username"
+            ));
+        }
+
+        let mut sender = Self::without_chat(api_url, bot_token)?;
+        sender.chat_id = chat_id;
+        Ok(sender)
+    }
+
+    /// Client for calls that only need the bot token, such as `getMe`.
+    pub fn without_chat(api_url: String, bot_token: String) -> Result<Self> {
+        if bot_token.trim().is_empty() {
+            log_error!("Bot token is required!");
+            return Err(anyhow!("Bot token is missing!"));
+        }
 
         if api_url.trim().is_empty() {
             log_error!("API URL is required!");
@@ -40,14 +110,49 @@ impl SendTg {
         Ok(Self {
             api_url,
             bot_token,
-            chat_id,
+            chat_id: String::new(),
             chat_name: "Unknown".to_string(),
-            client: Client::builder().timeout(None).build()?,
+            client: Client::builder()
+                .timeout(None)
+                .danger_accept_invalid_certs(accept_invalid_certs())
+                .build()?,
+            output_json: false,
+            print_message_ids: false,
+            dry_run: false,
+            caption_parse_mode: None,
+            message_entities: None,
+            spoiler_text: false,
+            blockquote: None,
+            disable_content_type_detection: false,
+            last_message_id: Mutex::new(None),
+            first_message_id: Mutex::new(None),
+            uploaded: Mutex::new((0, 0)),
+            progress_group: None,
+            upload_chunk_size: None,
+            file_cache: false,
         })
     }
 
     pub fn run(&mut self, args: &Args) -> Result<()> {
-        if args.media_paths.is_empty() && args.message.is_none() {
+        self.output_json = args.output_json;
+        self.print_message_ids = args.print_message_ids;
+        self.dry_run = args.dry_run;
+        self.caption_parse_mode = args.caption_parse_mode.clone();
+        self.message_entities = args.entities.clone();
+        self.spoiler_text = args.spoiler_text;
+        self.blockquote = if args.expandable_blockquote {
+            Some("<blockquote expandable>")
+        } else if args.blockquote {
+            Some("<blockquote>")
+        } else {
+            None
+        };
+        self.disable_content_type_detection = args.disable_content_type_detection;
+        self.upload_chunk_size = args.upload_chunk_size;
+        self.file_cache = !args.no_cache;
+        let started = Instant::now();
+
+        if !args.has_content() {
             if args.check {
                 let chat_id = self.chat_id.clone();
                 self.check(&chat_id, args.thread_id)?;
@@ -58,6 +163,10 @@ impl SendTg {
             ));
         }
 
+        if args.check_files {
+            utils::check_media_files(&args.media_paths)?;
+        }
+
         utils::validate_defaults(
             args.provided_api_url,
             args.provided_bot_token,
@@ -67,37 +176,552 @@ impl SendTg {
             &self.chat_id,
         );
 
-        if !args.media_paths.is_empty() {
+        if let Some((latitude, longitude)) = args.location {
             let chat_id = self.chat_id.clone();
-            self.send_media(
-                &chat_id,
-                &args.media_paths,
-                args.caption.as_deref(),
-                args.as_file,
-                args.no_group,
-                &args.buttons,
-                args.spoiler,
-                args.streaming,
-                args.delay_secs,
-                args.thread_id,
-            )?;
+            self.send_location(&chat_id, latitude, longitude, args)?;
+        }
+
+        if let Some(contact) = args.contact.as_ref() {
+            let chat_id = self.chat_id.clone();
+            self.send_contact(&chat_id, contact, args)?;
+        }
+
+        if let Some(emoji) = args.dice.as_deref() {
+            let chat_id = self.chat_id.clone();
+            self.send_dice(&chat_id, emoji, args)?;
+        }
+
+        if args.media_paths.is_empty() && args.message.is_none() {
             return Ok(());
         }
 
-        if let Some(message) = &args.message {
-            let reply_markup = utils::create_reply_markup(&args.buttons);
+        // Locations, contacts and dice sent above are not what --pin refers to.
+        *self.first_message_id.lock().unwrap() = None;
+        if !args.media_paths.is_empty() {
+            let chat_id = self.chat_id.clone();
+            let result = if args.video_note {
+                self.send_video_notes(&chat_id, args)
+            } else if args.voice {
+                self.send_voices(&chat_id, args)
+            } else {
+                self.send_media(&chat_id, args)
+            };
+            self.log_upload_summary(started.elapsed());
+            self.audit("send_media", &chat_id, &result);
+            result?;
+        } else if let Some(message) = &args.message {
+            let chat_id = self.chat_id.clone();
+            let result = self.send_text(&chat_id, message, args);
+            self.audit("send_message", &chat_id, &result);
+            result?;
+        } else {
+            return Err(anyhow!("No message or media provided."));
+        }
+
+        if args.pin {
             let chat_id = self.chat_id.clone();
+            self.pin_sent_message(&chat_id, args.pin_silent)?;
+        }
+
+        Ok(())
+    }
+
+    /// File part for a multipart upload. In large-file mode the file is read in
+    /// fixed-size chunks and the part carries its length, so the request is not
+    /// sent with chunked transfer encoding.
+    fn upload_part(
+        &self,
+        path: &Path,
+        file_name: &str,
+        group: Option<&utils::GroupProgress>,
+    ) -> Result<multipart::Part> {
+        let reader = utils::progress_reader_for_path(path, file_name, group)?;
+        let part = match self.upload_chunk_size {
+            Some(chunk_size) => {
+                let length = std::fs::metadata(path)?.len();
+                multipart::Part::reader_with_length(
+                    BufReader::with_capacity(chunk_size, reader),
+                    length,
+                )
+            }
+            None => multipart::Part::reader(reader),
+        };
+        Ok(part.file_name(file_name.to_string()))
+    }
+
+    fn record_upload(&self, bytes: u64, files: usize) {
+        FILES_SENT.fetch_add(files, Ordering::Relaxed);
+        let mut uploaded = self.uploaded.lock().unwrap();
+        uploaded.0 += bytes;
+        uploaded.1 += files;
+    }
+
+    fn last_message_id(&self) -> Option<i64> {
+        *self.last_message_id.lock().unwrap()
+    }
+
+    fn log_upload_summary(&self, elapsed: Duration) {
+        let (bytes, files) = *self.uploaded.lock().unwrap();
+        if files == 0 {
+            return;
+        }
+        let megabytes = bytes as f64 / 1_000_000.0;
+        let seconds = elapsed.as_secs_f64();
+        let speed = if seconds > 0.0 {
+            megabytes / seconds
+        } else {
+            0.0
+        };
+        log_info!(
+            "Uploaded {} file(s), {:.2} MB in {:.1}s ({:.2} MB/s)",
+            files,
+            megabytes,
+            seconds,
+            speed
+        );
+    }
+
+    fn pin_sent_message(&mut self, chat_id: &str, silent: bool) -> Result<()> {
+        if self.dry_run {
+            self.log_dry_run("pinChatMessage", "the sent message", 0);
+            return Ok(());
+        }
+
+        let Some(message_id) = *self.first_message_id.lock().unwrap() else {
+            log_warn!("No message ID was returned by Telegram; nothing to pin.");
+            return Ok(());
+        };
+
+        let payload = json!({
+            "chat_id": chat_id,
+            "message_id": message_id,
+            "disable_notification": silent,
+        });
+
+        let url = method_url(&self.api_url, &self.bot_token, "pinChatMessage");
+        let response = self.client.post(&url).json(&payload).send();
+
+        let result = self.handle_response("Failed to pin message:", response);
+        self.audit("pin_message", chat_id, &result);
+        result?;
+        log_info!("Message {} pinned in {}", message_id, self.chat_name);
+        Ok(())
+    }
+
+    /// Output flags for the commands that bypass `run()`.
+    fn set_output(&mut self, dry_run: bool, output_json: bool, print_message_ids: bool) {
+        self.dry_run = dry_run;
+        self.output_json = output_json;
+        self.print_message_ids = print_message_ids;
+    }
+
+    pub fn send_poll(&mut self, poll: &PollArgs) -> Result<()> {
+        self.set_output(poll.dry_run, poll.output_json, poll.print_message_ids);
+        let chat_id = self.chat_id.clone();
+        self.send_chat_action(&chat_id, "typing", poll.thread_id);
+
+        let options: Vec<Value> = poll
+            .options
+            .iter()
+            .map(|option| json!({ "text": option }))
+            .collect();
+
+        let mut payload = json!({
+            "chat_id": chat_id,
+            "question": poll.question,
+            "options": options,
+            "is_anonymous": poll.is_anonymous,
+            "type": poll.poll_type,
+            "disable_notification": poll.silent,
+        });
+
+        if let Some(id) = poll.thread_id {
+            payload["message_thread_id"] = json!(id);
+        }
+        if let Some(id) = poll.correct_option_id {
+            payload["correct_option_id"] = json!(id);
+        }
+        if let Some(explanation) = poll.explanation.as_ref() {
+            payload["explanation"] = json!(explanation);
+        }
+        if let Some(period) = poll.open_period {
+            payload["open_period"] = json!(period);
+        }
+
+        if self.dry_run {
+            self.log_dry_run("sendPoll", &poll.question, 0);
+            return Ok(());
+        }
+
+        let url = method_url(&self.api_url, &self.bot_token, "sendPoll");
+        let response = self.client.post(&url).json(&payload).send();
+
+        let body = self.handle_response("Failed to send poll:", response);
+        self.audit("send_poll", &chat_id, &body);
+        let body = body?;
+        let target = self.target_label(poll.thread_id);
+        log_info!("Poll sent to {}: {}", target, poll.question);
+        self.emit_response(&body);
+
+        if poll.pin {
+            self.pin_sent_message(&chat_id, poll.pin_silent)?;
+        }
+        Ok(())
+    }
+
+    pub fn forward_message(&mut self, forward: &ForwardArgs) -> Result<()> {
+        self.set_output(
+            forward.dry_run,
+            forward.output_json,
+            forward.print_message_ids,
+        );
+        let chat_id = self.chat_id.clone();
+        self.send_chat_action(&chat_id, "typing", forward.thread_id);
+
+        let mut payload = json!({
+            "chat_id": chat_id,
+            "from_chat_id": forward.from_chat_id,
+            "message_id": forward.message_id,
+            "disable_notification": forward.silent,
+            "protect_content": forward.protect_content,
+        });
+
+        if let Some(id) = forward.thread_id {
+            payload["message_thread_id"] = json!(id);
+        }
+
+        if self.dry_run {
+            self.log_dry_run(
+                "forwardMessage",
+                &format!(
+                    "message {} from {}",
+                    forward.message_id, forward.from_chat_id
+                ),
+                0,
+            );
+            return Ok(());
+        }
+
+        let url = method_url(&self.api_url, &self.bot_token, "forwardMessage");
+        let response = self.client.post(&url).json(&payload).send();
+
+        let body = self.handle_response("Failed to forward message:", response);
+        self.audit("forward_message", &chat_id, &body);
+        let body = body?;
+        let target = self.target_label(forward.thread_id);
+        log_info!(
+            "Message {} from {} forwarded to {}",
+            forward.message_id,
+            forward.from_chat_id,
+            target
+        );
+        self.emit_response(&body);
+
+        if forward.pin {
+            self.pin_sent_message(&chat_id, forward.pin_silent)?;
+        }
+        Ok(())
+    }
+
+    pub fn copy_message(&mut self, copy: &CopyArgs) -> Result<()> {
+        self.set_output(copy.dry_run, copy.output_json, copy.print_message_ids);
+        let chat_id = self.chat_id.clone();
+        self.send_chat_action(&chat_id, "typing", copy.thread_id);
+
+        let mut payload = json!({
+            "chat_id": chat_id,
+            "from_chat_id": copy.from_chat_id,
+            "message_id": copy.message_id,
+            "disable_notification": copy.silent,
+            "protect_content": copy.protect_content,
+        });
+
+        if let Some(id) = copy.thread_id {
+            payload["message_thread_id"] = json!(id);
+        }
+        if let Some(caption) = copy.caption.as_ref() {
+            payload["caption"] = json!(caption);
+            if let Some(mode) = copy.parse_mode.as_ref() {
+                payload["parse_mode"] = json!(mode);
+            }
+        }
+        if let Some(markup) = utils::create_reply_markup(&copy.buttons) {
+            payload["reply_markup"] = markup;
+        }
+
+        if self.dry_run {
+            self.log_dry_run(
+                "copyMessage",
+                &format!("message {} from {}", copy.message_id, copy.from_chat_id),
+                0,
+            );
+            return Ok(());
+        }
+
+        let url = method_url(&self.api_url, &self.bot_token, "copyMessage");
+        let response = self.client.post(&url).json(&payload).send();
+
+        let body = self.handle_response("Failed to copy message:", response);
+        self.audit("copy_message", &chat_id, &body);
+        let body = body?;
+        let target = self.target_label(copy.thread_id);
+        log_info!(
+            "Message {} from {} copied to {}",
+            copy.message_id,
+            copy.from_chat_id,
+            target
+        );
+        self.emit_response(&body);
+
+        if copy.pin {
+            self.pin_sent_message(&chat_id, copy.pin_silent)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete_messages(&mut self, delete: &DeleteArgs) -> Result<()> {
+        self.set_output(delete.dry_run, delete.output_json, delete.print_message_ids);
+        // deleteMessages accepts at most 100 IDs per call.
+        for chunk in delete.message_ids.chunks(100) {
+            let (method, payload) = match chunk {
+                [id] => (
+                    "deleteMessage",
+                    json!({ "chat_id": delete.chat_id, "message_id": id }),
+                ),
+                ids => (
+                    "deleteMessages",
+                    json!({ "chat_id": delete.chat_id, "message_ids": ids }),
+                ),
+            };
+
+            let ids = chunk
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            if self.dry_run {
+                self.log_dry_run(method, &format!("message(s) {}", ids), 0);
+                continue;
+            }
+
+            let url = method_url(&self.api_url, &self.bot_token, method);
+            let response = self.client.post(&url).json(&payload).send();
+
+            let body = self.handle_response("Failed to delete message:", response);
+            self.audit("delete_messages", &delete.chat_id, &body);
+            let body = body?;
+            log_info!(
+                "Deleted {} message(s) from {}: {}",
+                chunk.len(),
+                delete.chat_id,
+                ids
+            );
+            if self.output_json {
+                println!("{}", body.trim());
+            }
+            if self.print_message_ids {
+                for id in chunk {
+                    println!("{}", id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn edit_message(&mut self, edit: &EditArgs) -> Result<()> {
+        self.set_output(edit.dry_run, edit.output_json, edit.print_message_ids);
+        let chat_id = self.chat_id.clone();
+        let (method, mut payload) = match (edit.text.as_ref(), edit.caption.as_ref()) {
+            (Some(text), _) => (
+                "editMessageText",
+                json!({
+                    "chat_id": chat_id,
+                    "message_id": edit.message_id,
+                    "text": text.replace("\\n", "\n"),
+                }),
+            ),
+            (None, Some(caption)) => (
+                "editMessageCaption",
+                json!({
+                    "chat_id": chat_id,
+                    "message_id": edit.message_id,
+                    "caption": caption,
+                }),
+            ),
+            (None, None) => return Err(anyhow!("Nothing to edit: provide text or a caption")),
+        };
+
+        if let Some(mode) = edit.parse_mode.as_ref() {
+            payload["parse_mode"] = json!(mode);
+        }
+        if let Some(markup) = utils::create_reply_markup(&edit.buttons) {
+            payload["reply_markup"] = markup;
+        }
+
+        if self.dry_run {
+            self.log_dry_run(method, &format!("message {}", edit.message_id), 0);
+            return Ok(());
+        }
+
+        let url = method_url(&self.api_url, &self.bot_token, method);
+        let response = self.client.post(&url).json(&payload).send();
+
+        let body = self.handle_response("Failed to edit message:", response);
+        self.audit("edit_message", &chat_id, &body);
+        let body = body?;
+        log_info!("Message {} in {} edited", edit.message_id, chat_id);
+        self.emit_response(&body);
+        Ok(())
+    }
+
+    fn send_location(
+        &mut self,
+        chat_id: &str,
+        latitude: f64,
+        longitude: f64,
+        args: &Args,
+    ) -> Result<()> {
+        self.send_chat_action(chat_id, "find_location", args.thread_id);
+
+        let mut payload = json!({
+            "chat_id": chat_id,
+            "latitude": latitude,
+            "longitude": longitude,
+            "disable_notification": args.silent,
+        });
+
+        if let Some(id) = args.thread_id {
+            payload["message_thread_id"] = json!(id);
+        }
+        if let Some(period) = args.live_period {
+            payload["live_period"] = json!(period);
+        }
+
+        if self.dry_run {
+            self.log_dry_run("sendLocation", &format!("{},{}", latitude, longitude), 0);
+            return Ok(());
+        }
+
+        let url = method_url(&self.api_url, &self.bot_token, "sendLocation");
+        let response = self.client.post(&url).json(&payload).send();
+
+        let body = self.handle_response("Failed to send location:", response);
+        self.audit("send_location", chat_id, &body);
+        let body = body?;
+        let target = self.target_label(args.thread_id);
+        log_info!("Location sent to {}: {}, {}", target, latitude, longitude);
+        self.emit_response(&body);
+        Ok(())
+    }
+
+    fn send_contact(&mut self, chat_id: &str, contact: &ContactSpec, args: &Args) -> Result<()> {
+        self.send_chat_action(chat_id, "typing", args.thread_id);
+
+        let mut payload = json!({
+            "chat_id": chat_id,
+            "phone_number": contact.phone,
+            "first_name": contact.first_name,
+            "disable_notification": args.silent,
+        });
+
+        if let Some(last_name) = contact.last_name.as_ref() {
+            payload["last_name"] = json!(last_name);
+        }
+        if let Some(vcard) = contact.vcard.as_ref() {
+            payload["vcard"] = json!(vcard);
+        }
+        if let Some(id) = args.thread_id {
+            payload["message_thread_id"] = json!(id);
+        }
+
+        if self.dry_run {
+            self.log_dry_run("sendContact", &contact.first_name, 0);
+            return Ok(());
+        }
+
+        let url = method_url(&self.api_url, &self.bot_token, "sendContact");
+        let response = self.client.post(&url).json(&payload).send();
+
+        let body = self.handle_response("Failed to send contact:", response);
+        self.audit("send_contact", chat_id, &body);
+        let body = body?;
+        let target = self.target_label(args.thread_id);
+        log_info!("Contact sent to {}: {}", target, contact.first_name);
+        self.emit_response(&body);
+        Ok(())
+    }
+
+    fn send_dice(&mut self, chat_id: &str, emoji: &str, args: &Args) -> Result<()> {
+        self.send_chat_action(chat_id, "typing", args.thread_id);
+
+        let mut payload = json!({
+            "chat_id": chat_id,
+            "emoji": emoji,
+            "disable_notification": args.silent,
+        });
+
+        if let Some(id) = args.thread_id {
+            payload["message_thread_id"] = json!(id);
+        }
+
+        if self.dry_run {
+            self.log_dry_run("sendDice", emoji, 0);
+            return Ok(());
+        }
+
+        let url = method_url(&self.api_url, &self.bot_token, "sendDice");
+        let response = self.client.post(&url).json(&payload).send();
+
+        let body = self.handle_response("Failed to send dice:", response);
+        self.audit("send_dice", chat_id, &body);
+        let body = body?;
+        let target = self.target_label(args.thread_id);
+        log_info!("Dice {} sent to {}", emoji, target);
+        self.emit_response(&body);
+        Ok(())
+    }
+
+    /// Sends `message`, split into replies to each other with `--split-message`.
+    fn send_text(&mut self, chat_id: &str, message: &str, args: &Args) -> Result<()> {
+        let reply_markup = args.reply_markup();
+        let text = message.replace("\\n", "\n");
+        let chunks = if args.split_message {
+            utils::split_message(&text, MESSAGE_MAX_CHARS)
+        } else {
+            if utils::visible_len(&text) > MESSAGE_MAX_CHARS {
+                log_warn!(
+                    "Message is longer than {} characters; Telegram will reject it (use --split-message)",
+                    MESSAGE_MAX_CHARS
+                );
+            }
+            vec![text]
+        };
+        let last = chunks.len() - 1;
+        let mut reply_to = None;
+        for (index, chunk) in chunks.iter().enumerate() {
+            // Only the final part carries the keyboard so it sits under the whole text.
+            let markup = if index == last {
+                reply_markup.as_ref()
+            } else {
+                None
+            };
             self.send_message(
-                &chat_id,
-                message,
+                chat_id,
+                chunk,
                 args.silent,
-                reply_markup.as_ref(),
+                markup,
                 args.thread_id,
+                reply_to,
             )?;
-            return Ok(());
+            reply_to = self.last_message_id();
         }
+        Ok(())
+    }
 
-        Err(anyhow!("No message or media provided."))
+    fn audit<T>(&self, action: &str, chat_id: &str, result: &Result<T>) {
+        if !self.dry_run {
+            crate::audit::record(action, chat_id, result, &self.bot_token);
+        }
     }
 
     fn target_label(&self, thread_id: Option<i64>) -> String {
@@ -107,23 +731,36 @@ impl SendTg {
         self.chat_name.clone()
     }
 
-    fn send_message(
+    pub fn send_message(
         &mut self,
         chat_id: &str,
         message: &str,
         silent: bool,
         reply_markup: Option<&Value>,
         thread_id: Option<i64>,
+        reply_to: Option<i64>,
     ) -> Result<()> {
         self.send_chat_action(chat_id, "typing", thread_id);
 
+        let mut text = message.replace("\\n", "\n");
+        if self.spoiler_text {
+            text = format!("<tg-spoiler>{}</tg-spoiler>", text);
+        }
+        if let Some(open_tag) = self.blockquote {
+            text = format!("{}{}</blockquote>", open_tag, text);
+        }
         let mut payload = json!({
             "chat_id": chat_id,
-            "text": message.replace("\\n", "\n"),
-            "parse_mode": "HTML",
+            "text": text,
             "disable_notification": silent,
         });
 
+        // Entities replace parse_mode; Telegram rejects requests that set both.
+        match self.message_entities.as_ref() {
+            Some(entities) => payload["entities"] = entities.clone(),
+            None => payload["parse_mode"] = json!("HTML"),
+        }
+
         if let Some(id) = thread_id {
             payload["message_thread_id"] = json!(id);
         }
@@ -132,65 +769,185 @@ impl SendTg {
             payload["reply_markup"] = markup.clone();
         }
 
-        let url = format!("{}{}/sendMessage", self.api_url, self.bot_token);
+        if let Some(message_id) = reply_to {
+            payload["reply_parameters"] = json!({ "message_id": message_id });
+        }
+
+        if self.dry_run {
+            let size = serde_json::to_vec(&payload).map(|v| v.len()).unwrap_or(0);
+            self.log_dry_run(
+                "sendMessage",
+                &format!("text message ({} chars)", message.chars().count()),
+                size as u64,
+            );
+            return Ok(());
+        }
+
+        let url = method_url(&self.api_url, &self.bot_token, "sendMessage");
         let response = self.client.post(&url).json(&payload).send();
 
         match self.handle_response("Failed to send message:", response) {
-            Ok(_) => {
+            Ok(body) => {
                 let target = self.target_label(thread_id);
                 log_info!("Message sent to {}: {}", target, message);
+                self.emit_response(&body);
                 Ok(())
             }
             Err(err) => Err(err),
         }
     }
 
-    fn send_media(
-        &mut self,
-        chat_id: &str,
-        media_paths: &[PathBuf],
-        caption: Option<&str>,
-        as_file: bool,
-        no_group: bool,
-        buttons: &[ButtonSpec],
-        spoiler: bool,
-        streaming: bool,
-        delay_secs: Option<u64>,
-        thread_id: Option<i64>,
-    ) -> Result<()> {
-        let reply_markup_json = utils::create_reply_markup(buttons);
+    fn send_media(&mut self, chat_id: &str, args: &Args) -> Result<()> {
+        // A single caption keeps the original behaviour: it labels the album and is reused
+        // for media sent one by one. Several captions are matched to items in order.
+        let caption = match args.captions.as_slice() {
+            [single] => Some(single.as_str()),
+            _ => None,
+        };
+        let as_file = args.as_file;
+        let no_group_documents = args.no_group || args.no_group_documents;
+        let no_group_photos = args.no_group || args.no_group_photos;
+        let spoiler = args.spoiler;
+        let streaming = args.streaming;
+        let thread_id = args.thread_id;
+        let cloud_api = self.api_url.contains("api.telegram.org");
+
+        let metadata_options = args.metadata_options();
+        let custom_thumbnail = args
+            .thumbnail_path
+            .as_ref()
+            .map(|path| {
+                std::fs::read(path)
+                    .map_err(|err| anyhow!("Failed to read thumbnail {}: {}", path.display(), err))
+            })
+            .transpose()?;
+
+        let reply_markup_json = args.reply_markup();
         let reply_markup_text = reply_markup_json
             .as_ref()
             .and_then(|value| serde_json::to_string(value).ok());
 
         let mut media_items = Vec::new();
-        let mut caption_assigned = false;
         let mut send_calls = 0usize;
-        let delay = delay_secs.unwrap_or(0);
-        let maybe_delay = |calls: usize| {
-            if calls > 0 && delay > 0 {
-                log_info!(
-                    "Waiting {} s before next media request to reduce rate limiting",
-                    delay
-                );
-                std::thread::sleep(Duration::from_secs(delay));
+        let mut skipped = 0usize;
+        let mut failures = SendFailures::new(args.continue_on_error);
+        let maybe_delay = |calls: usize| pause_before_request(calls, args.delay_secs);
+        // Converted copies live until every request in this run has been sent.
+        let mut temp_files: Vec<tempfile::TempPath> = Vec::new();
+
+        for (position, listed_path) in args.media_paths.iter().enumerate() {
+            let mut stdin_name = None;
+            let source_path = if listed_path.as_os_str() == "-" {
+                let (temp, name) = utils::spool_stdin_media()?;
+                let path = temp.to_path_buf();
+                temp_files.push(temp);
+                stdin_name = Some(name);
+                path
+            } else {
+                listed_path.clone()
+            };
+            let original_path = &source_path;
+            if !utils::is_regular_file(original_path) {
+                log_error!("File not found: {}", original_path.display());
+                continue;
             }
-        };
 
-        for path in media_paths {
-            if !utils::is_regular_file(path) {
-                log_error!("File not found: {}", path.display());
+            if let Some(since) = args.since {
+                let modified = std::fs::metadata(original_path).and_then(|m| m.modified());
+                if modified.is_ok_and(|modified| modified < since) {
+                    log_info!(
+                        "Skipping {}: last modified before --since",
+                        original_path.display()
+                    );
+                    continue;
+                }
+            }
+
+            let size = std::fs::metadata(original_path)
+                .map(|m| m.len())
+                .unwrap_or(0);
+            if let Some(limit) = args.max_file_size
+                && size > limit
+            {
+                log_error!(
+                    "{} is {} bytes, over the --max-file-size limit of {} bytes",
+                    original_path.display(),
+                    size,
+                    limit
+                );
+                let label = original_path.display().to_string();
+                failures.record(&label, Err(anyhow!("exceeds --max-file-size")))?;
+                skipped += 1;
                 continue;
             }
 
-            let mime_type = utils::detect_mime_type(path);
-            let mut media_type = if as_file {
+            if cloud_api && size > CLOUD_API_MAX_BYTES {
+                log_warn!(
+                    "{} is larger than the 50 MB upload limit of the cloud Bot API; Telegram will likely reject it. Run a local Bot API server (see --local-api) for files up to 2000 MB.",
+                    original_path.display()
+                );
+            }
+
+            let mut file_name = stdin_name.unwrap_or_else(|| {
+                original_path
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("media")
+                    .to_string()
+            });
+            let mut mime_type = utils::detect_mime_type(original_path);
+
+            let mut upload_path = original_path.clone();
+            let mut force_document = as_file;
+            if !as_file && matches!(mime_type.as_deref(), Some("image/heic" | "image/heif")) {
+                log_info!("Converting {} to JPEG", original_path.display());
+                match utils::convert_heic_to_jpeg(original_path, &metadata_options) {
+                    Ok(temp) => {
+                        upload_path = temp.to_path_buf();
+                        temp_files.push(temp);
+                        file_name = Path::new(&file_name)
+                            .with_extension("jpg")
+                            .to_string_lossy()
+                            .into_owned();
+                        mime_type = Some("image/jpeg".to_string());
+                    }
+                    Err(err) => {
+                        log_error!(
+                            "Failed to convert {}; sending as document: {:#}",
+                            original_path.display(),
+                            err
+                        );
+                        force_document = true;
+                    }
+                }
+            }
+            // Never fall back to the original here: uploading it would leak the metadata.
+            if args.strip_exif
+                && matches!(mime_type.as_deref(), Some(mt) if mt.starts_with("image/"))
+            {
+                let temp = utils::strip_exif(&upload_path, &metadata_options).map_err(|err| {
+                    anyhow!(
+                        "Refusing to upload {} with its metadata intact: {:#}",
+                        original_path.display(),
+                        err
+                    )
+                })?;
+                log_info!("Stripped metadata from {}", original_path.display());
+                upload_path = temp.to_path_buf();
+                temp_files.push(temp);
+            }
+            let path = &upload_path;
+
+            let mut media_type = if force_document {
                 "document"
             } else {
                 utils::determine_media_type(mime_type.as_deref())
             };
 
-            if !matches!(media_type, "photo" | "video" | "audio" | "document") {
+            if !matches!(
+                media_type,
+                "photo" | "video" | "animation" | "audio" | "document"
+            ) {
                 log_error!(
                     "Unsupported media type for {}: {}",
                     path.display(),
@@ -222,10 +979,47 @@ impl SendTg {
                 matches!(mime_type.as_deref(), Some(mt) if mt.starts_with("video/"));
             let is_image_file =
                 matches!(mime_type.as_deref(), Some(mt) if mt.starts_with("image/"));
+            let is_audio_file =
+                matches!(mime_type.as_deref(), Some(mt) if mt.starts_with("audio/"));
+            let is_pdf_file = mime_type.as_deref() == Some("application/pdf");
 
-            let metadata = if is_video_file {
+            let metadata = if args.skip_thumbnail {
+                None
+            } else if let Some(bytes) = custom_thumbnail.as_ref() {
+                let thumbnail = Some(bytes.clone());
+                if is_video_file {
+                    Some(utils::MediaMetadata::Video(utils::VideoMetadata {
+                        duration: None,
+                        width: None,
+                        height: None,
+                        thumbnail,
+                    }))
+                } else if is_audio_file {
+                    let (duration, title, performer) =
+                        match utils::extract_audio_metadata(path, &metadata_options)
+                            .ok()
+                            .flatten()
+                        {
+                            Some(utils::MediaMetadata::Audio {
+                                duration,
+                                title,
+                                performer,
+                                ..
+                            }) => (duration, title, performer),
+                            _ => (None, None, None),
+                        };
+                    Some(utils::MediaMetadata::Audio {
+                        duration,
+                        title,
+                        performer,
+                        thumbnail,
+                    })
+                } else {
+                    Some(utils::MediaMetadata::Photo { thumbnail })
+                }
+            } else if is_video_file {
                 log_info!("Extracting video metadata from {}", path.display());
-                match utils::extract_video_metadata(path) {
+                match utils::extract_video_metadata(path, &metadata_options) {
                     Ok(meta) => {
                         if meta.is_some() {
                             log_info!(
@@ -237,7 +1031,7 @@ impl SendTg {
                     }
                     Err(err) => {
                         log_error!(
-                            "Failed to extract video metadata for {}: {}",
+                            "Failed to extract video metadata for {}: {:#}",
                             path.display(),
                             err
                         );
@@ -246,15 +1040,15 @@ impl SendTg {
                 }
             } else if is_image_file {
                 log_info!("Extracting photo thumbnail from {}", path.display());
-                match utils::extract_photo_metadata(path) {
+                match utils::extract_photo_metadata(path, &metadata_options) {
                     Ok(result) => {
-                        if let Some(ref thumb) = result {
-                            if thumb.is_some() {
-                                log_info!(
-                                    "Photo thumbnail generated successfully for {}",
-                                    path.display()
-                                );
-                            }
+                        if let Some(ref thumb) = result
+                            && thumb.is_some()
+                        {
+                            log_info!(
+                                "Photo thumbnail generated successfully for {}",
+                                path.display()
+                            );
                         }
                         result.map(|thumb_opt| utils::MediaMetadata::Photo {
                             thumbnail: thumb_opt,
@@ -262,7 +1056,33 @@ impl SendTg {
                     }
                     Err(err) => {
                         log_error!(
-                            "Failed to extract photo thumbnail for {}: {}",
+                            "Failed to extract photo thumbnail for {}: {:#}",
+                            path.display(),
+                            err
+                        );
+                        None
+                    }
+                }
+            } else if is_pdf_file {
+                log_info!("Rendering PDF thumbnail from {}", path.display());
+                match utils::extract_pdf_thumbnail(path, &metadata_options) {
+                    Ok(meta) => meta,
+                    Err(err) => {
+                        log_error!(
+                            "Failed to render PDF thumbnail for {}: {:#}",
+                            path.display(),
+                            err
+                        );
+                        None
+                    }
+                }
+            } else if is_audio_file {
+                log_info!("Extracting audio metadata from {}", path.display());
+                match utils::extract_audio_metadata(path, &metadata_options) {
+                    Ok(meta) => meta,
+                    Err(err) => {
+                        log_error!(
+                            "Failed to extract audio metadata for {}: {:#}",
                             path.display(),
                             err
                         );
@@ -273,45 +1093,115 @@ impl SendTg {
                 None
             };
 
-            let caption_for_item = if !caption_assigned {
-                caption.map(|c| c.to_string())
-            } else {
-                None
-            };
-            if caption_for_item.is_some() {
-                caption_assigned = true;
+            let mut caption_for_item = args.captions.get(media_items.len()).cloned();
+            if args.prepend_filename {
+                let heading = format!("<b>{}</b>", utils::escape_html(&file_name));
+                caption_for_item = Some(match caption_for_item {
+                    Some(text) if !text.is_empty() => format!("{}\n{}", heading, text),
+                    _ => heading,
+                });
+            }
+            if args.append_stats {
+                let stats = format!(
+                    "<code>Size: {} · {}</code>",
+                    utils::format_size(std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)),
+                    utils::escape_html(mime_type.as_deref().unwrap_or("application/octet-stream"))
+                );
+                caption_for_item = Some(match caption_for_item {
+                    Some(text) if !text.is_empty() => format!("{}\n{}", text, stats),
+                    _ => stats,
+                });
             }
 
             let part_name = format!("file{}", media_items.len());
 
             media_items.push(MediaItem {
                 media_type: media_type.to_string(),
-                file_name: path
-                    .file_name()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("media")
-                    .to_string(),
+                file_name,
                 path: path.clone(),
                 caption: caption_for_item,
-                spoiler: spoiler && matches!(media_type, "photo" | "video"),
+                spoiler: (spoiler || args.spoiler_items.contains(&(position + 1)))
+                    && matches!(media_type, "photo" | "video" | "animation"),
+                size: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
                 metadata,
                 part_name,
             });
         }
 
         if media_items.is_empty() {
-            return Ok(());
+            return failures.finish(skipped, "media requests");
+        }
+
+        if let Some(threads) = args.parallel.filter(|threads| *threads > 1)
+            && no_group_documents
+            && no_group_photos
+            && media_items.len() > 1
+        {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()?;
+            self.send_chat_action(chat_id, "upload_document", thread_id);
+            let paths: Vec<&Path> = media_items.iter().map(|item| item.path.as_path()).collect();
+            self.progress_group = Some(utils::GroupProgress::new(&paths));
+
+            // Every file is attempted; failures are collected instead of stopping the pool.
+            let this = &*self;
+            let results: Vec<Result<()>> = pool.install(|| {
+                media_items
+                    .par_iter()
+                    .map(|item| {
+                        this.send_single_media(
+                            chat_id,
+                            item,
+                            item.caption.as_deref().or(caption),
+                            reply_markup_text.as_deref(),
+                            item.spoiler,
+                            streaming,
+                            thread_id,
+                        )
+                    })
+                    .collect()
+            });
+            if let Some(group) = self.progress_group.take() {
+                group.finish();
+            }
+
+            for (item, result) in media_items.iter().zip(results) {
+                failures.collect(&item.file_name, result);
+            }
+            return failures.finish(media_items.len() + skipped, "media requests");
         }
 
         let mut index = 0;
         while index < media_items.len() {
+            // sendMediaGroup does not accept animations, so GIFs always go out on their own.
+            if media_items[index].media_type == "animation" {
+                let item = &media_items[index];
+                self.send_chat_action(chat_id, "upload_video", thread_id);
+                let caption_to_use = item.caption.as_deref().or(caption);
+                maybe_delay(send_calls);
+                let result = self.send_single_media(
+                    chat_id,
+                    item,
+                    caption_to_use,
+                    reply_markup_text.as_deref(),
+                    item.spoiler,
+                    streaming,
+                    thread_id,
+                );
+                failures.record(&item.file_name, result)?;
+                send_calls += 1;
+                index += 1;
+                continue;
+            }
+
             if media_items[index].media_type == "document" {
-                if no_group {
+                if no_group_documents {
                     let item = &media_items[index];
                     self.send_chat_action(chat_id, "upload_document", thread_id);
                     let caption_to_use = item.caption.as_deref().or(caption);
                     maybe_delay(send_calls);
-                    self.send_single_media(
+                    let result = self.send_single_media(
                         chat_id,
                         item,
                         caption_to_use,
@@ -319,7 +1209,8 @@ impl SendTg {
                         item.spoiler,
                         streaming,
                         thread_id,
-                    )?;
+                    );
+                    failures.record(&item.file_name, result)?;
                     send_calls += 1;
                     index += 1;
                     continue;
@@ -339,7 +1230,7 @@ impl SendTg {
                     self.send_chat_action(chat_id, "upload_document", thread_id);
                     let caption_to_use = item.caption.as_deref().or(caption);
                     maybe_delay(send_calls);
-                    self.send_single_media(
+                    let result = self.send_single_media(
                         chat_id,
                         item,
                         caption_to_use,
@@ -347,7 +1238,8 @@ impl SendTg {
                         item.spoiler,
                         streaming,
                         thread_id,
-                    )?;
+                    );
+                    failures.record(&item.file_name, result)?;
                     send_calls += 1;
                     continue;
                 }
@@ -358,13 +1250,14 @@ impl SendTg {
                     .map(|&idx| media_items[idx].clone())
                     .collect();
                 maybe_delay(send_calls);
-                self.send_media_group(
+                let result = self.send_media_group(
                     chat_id,
                     &chunk_items,
                     reply_markup_text.as_deref(),
                     streaming,
                     thread_id,
-                )?;
+                );
+                failures.record(&group_label(&chunk_items), result)?;
                 send_calls += 1;
                 continue;
             }
@@ -372,7 +1265,10 @@ impl SendTg {
             let mut chunk_indices = Vec::new();
             while index < media_items.len()
                 && chunk_indices.len() < 10
-                && media_items[index].media_type != "document"
+                && !matches!(
+                    media_items[index].media_type.as_str(),
+                    "document" | "animation"
+                )
             {
                 chunk_indices.push(index);
                 index += 1;
@@ -382,14 +1278,14 @@ impl SendTg {
                 continue;
             }
 
-            if no_group || chunk_indices.len() == 1 {
+            if no_group_photos || chunk_indices.len() == 1 {
                 for idx in chunk_indices {
                     let item = &media_items[idx];
                     let action = format!("upload_{}", item.media_type.to_lowercase());
                     self.send_chat_action(chat_id, &action, thread_id);
                     let caption_to_use = item.caption.as_deref().or(caption);
                     maybe_delay(send_calls);
-                    self.send_single_media(
+                    let result = self.send_single_media(
                         chat_id,
                         item,
                         caption_to_use,
@@ -397,7 +1293,8 @@ impl SendTg {
                         item.spoiler,
                         streaming,
                         thread_id,
-                    )?;
+                    );
+                    failures.record(&item.file_name, result)?;
                     send_calls += 1;
                 }
                 continue;
@@ -411,17 +1308,18 @@ impl SendTg {
                 .map(|&idx| media_items[idx].clone())
                 .collect();
             maybe_delay(send_calls);
-            self.send_media_group(
+            let result = self.send_media_group(
                 chat_id,
                 &chunk_items,
                 reply_markup_text.as_deref(),
                 streaming,
                 thread_id,
-            )?;
+            );
+            failures.record(&group_label(&chunk_items), result)?;
             send_calls += 1;
         }
 
-        Ok(())
+        failures.finish(send_calls + skipped, "media requests")
     }
 
     fn send_media_group(
@@ -440,18 +1338,26 @@ impl SendTg {
                 media_type: item.media_type.clone(),
                 media: format!("attach://{}", item.part_name),
                 caption: item.caption.clone(),
+                parse_mode: item.caption.as_ref().and(self.caption_parse_mode.clone()),
                 has_spoiler: if item.spoiler { Some(true) } else { None },
                 width: None,
                 height: None,
                 duration: None,
                 thumbnail: None,
                 supports_streaming: None,
+                disable_content_type_detection: None,
+                title: None,
+                performer: None,
             };
 
             if streaming && item.media_type == "video" {
                 entry.supports_streaming = Some(true);
             }
 
+            if self.disable_content_type_detection && item.media_type == "document" {
+                entry.disable_content_type_detection = Some(true);
+            }
+
             if let Some(metadata) = item.metadata.as_ref() {
                 match metadata {
                     utils::MediaMetadata::Video(video_meta) => {
@@ -464,7 +1370,25 @@ impl SendTg {
                             thumbnails.push((name, bytes.clone()));
                         }
                     }
-                    utils::MediaMetadata::Photo { thumbnail } => {
+                    utils::MediaMetadata::Photo { thumbnail }
+                    | utils::MediaMetadata::Pdf { thumbnail } => {
+                        if let Some(bytes) = thumbnail.as_ref() {
+                            let name = format!("{}_thumb", item.part_name);
+                            entry.thumbnail = Some(format!("attach://{}", name));
+                            thumbnails.push((name, bytes.clone()));
+                        }
+                    }
+                    utils::MediaMetadata::Audio {
+                        duration,
+                        title,
+                        performer,
+                        thumbnail,
+                    } => {
+                        if item.media_type == "audio" {
+                            entry.duration = *duration;
+                            entry.title = title.clone();
+                            entry.performer = performer.clone();
+                        }
                         if let Some(bytes) = thumbnail.as_ref() {
                             let name = format!("{}_thumb", item.part_name);
                             entry.thumbnail = Some(format!("attach://{}", name));
@@ -472,47 +1396,304 @@ impl SendTg {
                         }
                     }
                 }
-            }
+            }
+
+            media_payload.push(entry);
+        }
+
+        if self.dry_run {
+            let files: Vec<&str> = items.iter().map(|item| item.file_name.as_str()).collect();
+            let size = items.iter().map(MediaItem::payload_size).sum();
+            self.log_dry_run("sendMediaGroup", &files.join(", "), size);
+            return Ok(());
+        }
+
+        let url = method_url(&self.api_url, &self.bot_token, "sendMediaGroup");
+        let body = self.send_multipart_with_retry("Failed to send media group:", &url, || {
+            // Rebuild the multipart form each attempt to keep streams fresh.
+            let mut rebuilt_form = multipart::Form::new()
+                .text("chat_id", chat_id.to_string())
+                .text("media", serde_json::to_string(&media_payload)?);
+
+            if let Some(id) = thread_id {
+                rebuilt_form = rebuilt_form.text("message_thread_id", id.to_string());
+            }
+
+            if let Some(markup) = reply_markup {
+                rebuilt_form = rebuilt_form.text("reply_markup", markup.to_string());
+            }
+
+            let paths: Vec<&Path> = items.iter().map(|item| item.path.as_path()).collect();
+            let group_progress = utils::GroupProgress::new(&paths);
+            for item in items {
+                let part = self.upload_part(&item.path, &item.file_name, Some(&group_progress))?;
+                rebuilt_form = rebuilt_form.part(item.part_name.clone(), part);
+            }
+
+            for (name, bytes) in &thumbnails {
+                let part = multipart::Part::bytes(bytes.clone())
+                    .file_name(format!("{}.jpg", name))
+                    .mime_str("image/jpeg")?;
+                rebuilt_form = rebuilt_form.part(name.clone(), part);
+            }
+
+            Ok(rebuilt_form)
+        })?;
+
+        let target = self.target_label(thread_id);
+        log_info!("{} items sent to {} as media group", items.len(), target);
+        self.record_upload(items.iter().map(MediaItem::payload_size).sum(), items.len());
+        self.emit_response(&body);
+        Ok(())
+    }
+
+    fn send_video_notes(&mut self, chat_id: &str, args: &Args) -> Result<()> {
+        for path in &args.media_paths {
+            if !utils::is_regular_file(path) {
+                return Err(anyhow!("File not found: {}", path.display()));
+            }
+            let mime_type = utils::detect_mime_type(path);
+            if !matches!(mime_type.as_deref(), Some(mt) if mt.starts_with("video/")) {
+                return Err(anyhow!(
+                    "--video-note requires video files, but {} is {}",
+                    path.display(),
+                    mime_type.as_deref().unwrap_or("of unknown type")
+                ));
+            }
+        }
+
+        let metadata_options = args.metadata_options();
+        let mut failures = SendFailures::new(args.continue_on_error);
+        for (index, path) in args.media_paths.iter().enumerate() {
+            pause_before_request(index, args.delay_secs);
+
+            let metadata = if args.skip_thumbnail {
+                None
+            } else {
+                log_info!("Extracting video metadata from {}", path.display());
+                match utils::extract_video_metadata(path, &metadata_options) {
+                    Ok(meta) => meta,
+                    Err(err) => {
+                        log_error!(
+                            "Failed to extract video metadata for {}: {:#}",
+                            path.display(),
+                            err
+                        );
+                        None
+                    }
+                }
+            };
+
+            self.send_chat_action(chat_id, "upload_video_note", args.thread_id);
+            let result = self.send_video_note(chat_id, path, metadata.as_ref(), args);
+            failures.record(&path.display().to_string(), result)?;
+        }
+
+        failures.finish(args.media_paths.len(), "video notes")
+    }
+
+    fn send_video_note(
+        &self,
+        chat_id: &str,
+        path: &std::path::Path,
+        metadata: Option<&utils::VideoMetadata>,
+        args: &Args,
+    ) -> Result<()> {
+        let file_name = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("video_note")
+            .to_string();
+
+        if self.dry_run {
+            let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            self.log_dry_run("sendVideoNote", &file_name, size);
+            return Ok(());
+        }
+
+        let endpoint = method_url(&self.api_url, &self.bot_token, "sendVideoNote");
+        let body =
+            self.send_multipart_with_retry("Failed to send video note:", &endpoint, || {
+                let mut form = multipart::Form::new()
+                    .part("video_note", self.upload_part(path, &file_name, None)?)
+                    .text("chat_id", chat_id.to_string());
+
+                if let Some(id) = args.thread_id {
+                    form = form.text("message_thread_id", id.to_string());
+                }
+                if args.silent {
+                    form = form.text("disable_notification", "true");
+                }
+
+                if let Some(meta) = metadata {
+                    if let Some(duration) = meta.duration {
+                        form = form.text("duration", duration.to_string());
+                    }
+                    // Video notes are square; Telegram takes a single diameter as `length`.
+                    let length = match (meta.width, meta.height) {
+                        (Some(w), Some(h)) => Some(w.min(h)),
+                        (w, h) => w.or(h),
+                    };
+                    if let Some(length) = length {
+                        form = form.text("length", length.to_string());
+                    }
+                    if let Some(bytes) = meta.thumbnail.as_ref() {
+                        let part = multipart::Part::bytes(bytes.clone())
+                            .file_name("thumbnail.jpg")
+                            .mime_str("image/jpeg")?;
+                        form = form.part("thumbnail", part);
+                    }
+                }
+
+                Ok(form)
+            })?;
+
+        let target = self.target_label(args.thread_id);
+        log_info!("Video note sent to {}: {}", target, file_name);
+        self.record_upload(std::fs::metadata(path).map(|m| m.len()).unwrap_or(0), 1);
+        self.emit_response(&body);
+        Ok(())
+    }
+
+    fn send_voices(&mut self, chat_id: &str, args: &Args) -> Result<()> {
+        for path in &args.media_paths {
+            if !utils::is_regular_file(path) {
+                return Err(anyhow!("File not found: {}", path.display()));
+            }
+            let mime_type = utils::detect_mime_type(path);
+            if !matches!(mime_type.as_deref(), Some(mt) if mt.starts_with("audio/")) {
+                return Err(anyhow!(
+                    "--voice requires audio files, but {} is {}",
+                    path.display(),
+                    mime_type.as_deref().unwrap_or("of unknown type")
+                ));
+            }
+        }
+
+        let metadata_options = args.metadata_options();
+        let reply_markup = args
+            .reply_markup()
+            .and_then(|value| serde_json::to_string(&value).ok());
+        let mut failures = SendFailures::new(args.continue_on_error);
+
+        for (index, path) in args.media_paths.iter().enumerate() {
+            pause_before_request(index, args.delay_secs);
+
+            let duration = if args.skip_thumbnail {
+                None
+            } else {
+                match utils::probe_duration(path, &metadata_options) {
+                    Ok(duration) => duration,
+                    Err(err) => {
+                        log_error!("Failed to read duration for {}: {:#}", path.display(), err);
+                        None
+                    }
+                }
+            };
 
-            media_payload.push(entry);
-        }
+            let is_ogg = matches!(
+                utils::detect_mime_type(path).as_deref(),
+                Some("audio/ogg" | "audio/opus")
+            );
+            let converted = if is_ogg {
+                None
+            } else {
+                log_info!("Converting {} to OGG Opus for voice upload", path.display());
+                match utils::convert_to_ogg_opus(path, &metadata_options) {
+                    Ok(bytes) => Some(bytes),
+                    Err(err) => {
+                        let err = anyhow!("cannot convert to OGG Opus: {:#}", err);
+                        failures.record(&path.display().to_string(), Err(err))?;
+                        continue;
+                    }
+                }
+            };
 
-        let url = format!("{}{}/sendMediaGroup", self.api_url, self.bot_token);
-        self.send_multipart_with_retry("Failed to send media group:", &url, || {
-            // Rebuild the multipart form each attempt to keep streams fresh.
-            let mut rebuilt_form = multipart::Form::new()
-                .text("chat_id", chat_id.to_string())
-                .text("media", serde_json::to_string(&media_payload)?);
+            let caption = args.captions.get(index).map(String::as_str);
 
-            if let Some(id) = thread_id {
-                rebuilt_form = rebuilt_form.text("message_thread_id", id.to_string());
-            }
+            self.send_chat_action(chat_id, "upload_voice", args.thread_id);
+            let result = self.send_voice(
+                chat_id,
+                path,
+                converted,
+                duration,
+                caption,
+                reply_markup.as_deref(),
+                args,
+            );
+            failures.record(&path.display().to_string(), result)?;
+        }
 
-            if let Some(markup) = reply_markup {
-                rebuilt_form = rebuilt_form.text("reply_markup", markup.to_string());
-            }
+        failures.finish(args.media_paths.len(), "voice messages")
+    }
 
-            for item in items {
-                let reader = utils::progress_reader_for_path(&item.path, &item.file_name)?;
-                let part = multipart::Part::reader(reader).file_name(item.file_name.clone());
-                rebuilt_form = rebuilt_form.part(item.part_name.clone(), part);
-            }
+    #[allow(clippy::too_many_arguments)]
+    fn send_voice(
+        &self,
+        chat_id: &str,
+        path: &std::path::Path,
+        converted: Option<Vec<u8>>,
+        duration: Option<u64>,
+        caption: Option<&str>,
+        reply_markup: Option<&str>,
+        args: &Args,
+    ) -> Result<()> {
+        let file_name = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("voice")
+            .to_string();
+
+        let upload_size = match converted.as_ref() {
+            Some(bytes) => bytes.len() as u64,
+            None => std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        };
+        if self.dry_run {
+            self.log_dry_run("sendVoice", &file_name, upload_size);
+            return Ok(());
+        }
 
-            for (name, bytes) in &thumbnails {
-                let part = multipart::Part::bytes(bytes.clone())
-                    .file_name(format!("{}.jpg", name))
-                    .mime_str("image/jpeg")?;
-                rebuilt_form = rebuilt_form.part(name.clone(), part);
-            }
+        let endpoint = method_url(&self.api_url, &self.bot_token, "sendVoice");
+        let body =
+            self.send_multipart_with_retry("Failed to send voice message:", &endpoint, || {
+                let part = match converted.as_ref() {
+                    Some(bytes) => multipart::Part::bytes(bytes.clone())
+                        .file_name(format!("{}.ogg", file_name))
+                        .mime_str("audio/ogg")?,
+                    None => self.upload_part(path, &file_name, None)?,
+                };
+
+                let mut form = multipart::Form::new()
+                    .part("voice", part)
+                    .text("chat_id", chat_id.to_string());
+
+                if let Some(id) = args.thread_id {
+                    form = form.text("message_thread_id", id.to_string());
+                }
+                if let Some(duration) = duration {
+                    form = form.text("duration", duration.to_string());
+                }
+                if let Some(caption) = caption {
+                    form = form.text("caption", caption.to_string());
+                }
+                if let Some(markup) = reply_markup {
+                    form = form.text("reply_markup", markup.to_string());
+                }
+                if args.silent {
+                    form = form.text("disable_notification", "true");
+                }
 
-            Ok(rebuilt_form)
-        })?;
+                Ok(form)
+            })?;
 
-        let target = self.target_label(thread_id);
-        log_info!("{} items sent to {} as media group", items.len(), target);
+        let target = self.target_label(args.thread_id);
+        log_info!("Voice message sent to {}: {}", target, file_name);
+        self.record_upload(upload_size, 1);
+        self.emit_response(&body);
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn send_single_media(
         &self,
         chat_id: &str,
@@ -523,82 +1704,212 @@ impl SendTg {
         streaming: bool,
         thread_id: Option<i64>,
     ) -> Result<()> {
-        let endpoint = format!(
-            "{}{}/send{}",
-            self.api_url,
-            self.bot_token,
-            utils::capitalize(&item.media_type)
-        );
-        self.send_multipart_with_retry("Failed to send media file:", &endpoint, || {
-            let reader = utils::progress_reader_for_path(&item.path, &item.file_name)?;
-
-            let mut fresh_form = multipart::Form::new().part(
-                item.media_type.clone(),
-                multipart::Part::reader(reader).file_name(item.file_name.clone()),
-            );
-
-            fresh_form = fresh_form.text("chat_id", chat_id.to_string());
+        let method = format!("send{}", utils::capitalize(&item.media_type));
+        if self.dry_run {
+            self.log_dry_run(&method, &item.file_name, item.payload_size());
+            return Ok(());
+        }
 
-            if let Some(id) = thread_id {
-                fresh_form = fresh_form.text("message_thread_id", id.to_string());
+        let bot_id = self.bot_token.split(':').next().unwrap_or_default();
+        let hash = if self.file_cache {
+            match crate::cache::hash_file(&item.path) {
+                Ok(hash) => Some(hash),
+                Err(err) => {
+                    log_warn!("File cache disabled for {}: {:#}", item.file_name, err);
+                    None
+                }
             }
+        } else {
+            None
+        };
+        let cached_id = hash
+            .as_deref()
+            .and_then(|hash| crate::cache::lookup(hash, &item.media_type, bot_id));
+
+        let endpoint = method_url(&self.api_url, &self.bot_token, &method);
+        let send = |file_id: Option<&str>| {
+            self.send_multipart_with_retry("Failed to send media file:", &endpoint, || {
+                let mut fresh_form = match file_id {
+                    Some(id) => {
+                        multipart::Form::new().text(item.media_type.clone(), id.to_string())
+                    }
+                    None => multipart::Form::new().part(
+                        item.media_type.clone(),
+                        self.upload_part(
+                            &item.path,
+                            &item.file_name,
+                            self.progress_group.as_ref(),
+                        )?,
+                    ),
+                };
+
+                fresh_form = fresh_form.text("chat_id", chat_id.to_string());
+
+                if let Some(id) = thread_id {
+                    fresh_form = fresh_form.text("message_thread_id", id.to_string());
+                }
 
-            if streaming && item.media_type == "video" {
-                fresh_form = fresh_form.text("supports_streaming", "true");
-            }
+                if streaming && item.media_type == "video" {
+                    fresh_form = fresh_form.text("supports_streaming", "true");
+                }
 
-            if let Some(metadata) = item.metadata.as_ref() {
-                match metadata {
-                    utils::MediaMetadata::Video(video_meta) => {
-                        if let Some(duration) = video_meta.duration {
-                            fresh_form = fresh_form.text("duration", duration.to_string());
-                        }
-                        if let Some(width) = video_meta.width {
-                            fresh_form = fresh_form.text("width", width.to_string());
+                if self.disable_content_type_detection && item.media_type == "document" {
+                    fresh_form = fresh_form.text("disable_content_type_detection", "true");
+                }
+
+                if let Some(metadata) = item.metadata.as_ref() {
+                    match metadata {
+                        utils::MediaMetadata::Video(video_meta) => {
+                            if let Some(duration) = video_meta.duration {
+                                fresh_form = fresh_form.text("duration", duration.to_string());
+                            }
+                            if let Some(width) = video_meta.width {
+                                fresh_form = fresh_form.text("width", width.to_string());
+                            }
+                            if let Some(height) = video_meta.height {
+                                fresh_form = fresh_form.text("height", height.to_string());
+                            }
+                            if let Some(bytes) = video_meta.thumbnail.as_ref() {
+                                let part = multipart::Part::bytes(bytes.clone())
+                                    .file_name("thumbnail.jpg")
+                                    .mime_str("image/jpeg")?;
+                                fresh_form = fresh_form.part("thumbnail", part);
+                            }
                         }
-                        if let Some(height) = video_meta.height {
-                            fresh_form = fresh_form.text("height", height.to_string());
+                        utils::MediaMetadata::Photo { thumbnail }
+                        | utils::MediaMetadata::Pdf { thumbnail } => {
+                            if let Some(bytes) = thumbnail.as_ref() {
+                                let part = multipart::Part::bytes(bytes.clone())
+                                    .file_name("thumbnail.jpg")
+                                    .mime_str("image/jpeg")?;
+                                fresh_form = fresh_form.part("thumbnail", part);
+                            }
                         }
-                        if let Some(bytes) = video_meta.thumbnail.as_ref() {
-                            let part = multipart::Part::bytes(bytes.clone())
-                                .file_name("thumbnail.jpg")
-                                .mime_str("image/jpeg")?;
-                            fresh_form = fresh_form.part("thumbnail", part);
+                        utils::MediaMetadata::Audio {
+                            duration,
+                            title,
+                            performer,
+                            thumbnail,
+                        } => {
+                            if item.media_type == "audio" {
+                                if let Some(duration) = duration {
+                                    fresh_form = fresh_form.text("duration", duration.to_string());
+                                }
+                                if let Some(title) = title {
+                                    fresh_form = fresh_form.text("title", title.clone());
+                                }
+                                if let Some(performer) = performer {
+                                    fresh_form = fresh_form.text("performer", performer.clone());
+                                }
+                            }
+                            if let Some(bytes) = thumbnail.as_ref() {
+                                let part = multipart::Part::bytes(bytes.clone())
+                                    .file_name("thumbnail.jpg")
+                                    .mime_str("image/jpeg")?;
+                                fresh_form = fresh_form.part("thumbnail", part);
+                            }
                         }
                     }
-                    utils::MediaMetadata::Photo { thumbnail } => {
-                        if let Some(bytes) = thumbnail.as_ref() {
-                            let part = multipart::Part::bytes(bytes.clone())
-                                .file_name("thumbnail.jpg")
-                                .mime_str("image/jpeg")?;
-                            fresh_form = fresh_form.part("thumbnail", part);
-                        }
+                }
+
+                if let Some(caption) = caption {
+                    fresh_form = fresh_form.text("caption", caption.to_string());
+                    if let Some(mode) = self.caption_parse_mode.as_ref() {
+                        fresh_form = fresh_form.text("parse_mode", mode.clone());
                     }
                 }
-            }
+                if let Some(markup) = reply_markup {
+                    fresh_form = fresh_form.text("reply_markup", markup.to_string());
+                }
+                if spoiler && matches!(item.media_type.as_str(), "photo" | "video" | "animation") {
+                    fresh_form = fresh_form.text("has_spoiler", "true".to_string());
+                }
 
-            if let Some(caption) = caption {
-                fresh_form = fresh_form.text("caption", caption.to_string());
-            }
-            if let Some(markup) = reply_markup {
-                fresh_form = fresh_form.text("reply_markup", markup.to_string());
-            }
-            if spoiler && matches!(item.media_type.as_str(), "photo" | "video") {
-                fresh_form = fresh_form.text("has_spoiler", "true".to_string());
-            }
+                Ok(fresh_form)
+            })
+        };
 
-            Ok(fresh_form)
-        })?;
+        let cached_body = cached_id.as_deref().and_then(|file_id| {
+            log_info!("Reusing cached file_id for {}", item.file_name);
+            match send(Some(file_id)) {
+                Ok(body) => Some(body),
+                Err(_) => {
+                    log_warn!(
+                        "Cached file_id for {} was rejected; uploading",
+                        item.file_name
+                    );
+                    None
+                }
+            }
+        });
+        let from_cache = cached_body.is_some();
+        let body = match cached_body {
+            Some(body) => body,
+            None => send(None)?,
+        };
 
         let target = self.target_label(thread_id);
         log_info!("Single media file sent to {}: {}", target, item.file_name);
+        if from_cache {
+            FILES_SENT.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.record_upload(item.payload_size(), 1);
+            if let (Some(hash), Some(file_id)) = (
+                hash.as_deref(),
+                crate::cache::file_id_from_response(&body, &item.media_type),
+            ) && let Err(err) = crate::cache::store(hash, &item.media_type, bot_id, &file_id)
+            {
+                log_warn!("Failed to update the file cache: {:#}", err);
+            }
+        }
+        self.emit_response(&body);
         Ok(())
     }
 
+    fn emit_response(&self, body: &str) {
+        let message_ids = utils::extract_message_ids(body);
+        if let Some(&id) = message_ids.first() {
+            *self.last_message_id.lock().unwrap() = Some(id);
+            self.first_message_id.lock().unwrap().get_or_insert(id);
+        }
+        if self.output_json {
+            println!("{}", body.trim());
+        }
+        let format = crate::output::format();
+        if !format.is_text() {
+            format.print_record(&[
+                ("status", json!("sent")),
+                ("chat_id", json!(self.chat_id)),
+                ("message_ids", json!(message_ids)),
+            ]);
+        }
+        if self.print_message_ids {
+            for id in message_ids {
+                println!("{}", id);
+            }
+        }
+    }
+
+    fn log_dry_run(&self, method: &str, what: &str, payload_bytes: u64) {
+        log_info!(
+            "Dry run: would call {}{}/{} for {} in {} (~{} bytes)",
+            self.api_url,
+            utils::redact_token(&self.bot_token),
+            method,
+            what,
+            self.chat_id,
+            payload_bytes
+        );
+    }
+
     fn send_chat_action(&mut self, chat_id: &str, action: &str, thread_id: Option<i64>) {
         self.chat_name = "Unknown".to_string();
 
-        let action_url = format!("{}{}/sendChatAction", self.api_url, self.bot_token);
+        if self.dry_run {
+            return;
+        }
+
+        let action_url = method_url(&self.api_url, &self.bot_token, "sendChatAction");
         let mut form = vec![
             ("chat_id".to_string(), chat_id.to_string()),
             ("action".to_string(), action.to_string()),
@@ -612,7 +1923,7 @@ impl SendTg {
             log_debug!("{}", err);
         }
 
-        let chat_url = format!("{}{}/getChat", self.api_url, self.bot_token);
+        let chat_url = method_url(&self.api_url, &self.bot_token, "getChat");
         let response = self
             .client
             .get(&chat_url)
@@ -641,11 +1952,11 @@ impl SendTg {
                         }
                         if let Some(first) = result.first_name {
                             let mut full = first;
-                            if let Some(last) = result.last_name {
-                                if !last.trim().is_empty() {
-                                    full.push(' ');
-                                    full.push_str(&last);
-                                }
+                            if let Some(last) = result.last_name
+                                && !last.trim().is_empty()
+                            {
+                                full.push(' ');
+                                full.push_str(&last);
                             }
                             let trimmed = full.trim();
                             self.chat_name = if trimmed.is_empty() {
@@ -655,6 +1966,10 @@ impl SendTg {
                             };
                             return;
                         }
+                        if let Some(username) = result.username {
+                            self.chat_name = format!("@{}", username);
+                            return;
+                        }
                     }
                 } else if let Some(description) = chat_info.description {
                     self.chat_name = format!("Error: {}", description);
@@ -668,7 +1983,141 @@ impl SendTg {
         }
     }
 
+    pub fn get_chat_info(&self) -> Result<()> {
+        let url = method_url(&self.api_url, &self.bot_token, "getChat");
+        let response = self
+            .client
+            .get(&url)
+            .query(&[("chat_id", self.chat_id.as_str())])
+            .send();
+        let body = self.handle_response("Failed to get chat:", response)?;
+        let value: Value = serde_json::from_str(&body)
+            .map_err(|err| anyhow!("Failed to parse getChat response: {}", err))?;
+        let chat = &value["result"];
+
+        // Member counts are not part of getChat; a failure here is not fatal.
+        let count_url = method_url(&self.api_url, &self.bot_token, "getChatMemberCount");
+        let member_count = self
+            .handle_response(
+                "Failed to get member count:",
+                self.client
+                    .get(&count_url)
+                    .query(&[("chat_id", self.chat_id.as_str())])
+                    .send(),
+            )
+            .ok()
+            .and_then(|body| serde_json::from_str::<Value>(&body).ok())
+            .and_then(|value| value["result"].as_u64());
+
+        let name = chat["title"]
+            .as_str()
+            .map(ToString::to_string)
+            .unwrap_or_else(|| {
+                [chat["first_name"].as_str(), chat["last_name"].as_str()]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            });
+        let text = |key: &str| chat[key].as_str().unwrap_or("<not set>").to_string();
+
+        println!("ID          : {}", chat["id"]);
+        println!("Type        : {}", text("type"));
+        println!(
+            "Name        : {}",
+            if name.is_empty() { "<not set>" } else { &name }
+        );
+        if let Some(username) = chat["username"].as_str() {
+            println!("Username    : @{}", username);
+        }
+        println!("Description : {}", text("description"));
+        println!(
+            "Members     : {}",
+            member_count
+                .map(|count| count.to_string())
+                .unwrap_or_else(|| "<unknown>".to_string())
+        );
+        println!("Invite link : {}", text("invite_link"));
+        Ok(())
+    }
+
+    fn get_me(&self) -> Result<Value> {
+        let url = method_url(&self.api_url, &self.bot_token, "getMe");
+        let response = self.client.get(&url).send();
+        let body = self.handle_response("Failed to verify bot token:", response)?;
+        let value: Value = serde_json::from_str(&body)
+            .map_err(|err| anyhow!("Failed to parse getMe response: {}", err))?;
+        Ok(value["result"].clone())
+    }
+
+    pub fn check_token(&self) -> Result<()> {
+        let start = Instant::now();
+        let bot = self.get_me()?;
+        let elapsed = start.elapsed().as_millis();
+
+        let id = bot["id"].as_i64().unwrap_or_default();
+        let username = bot["username"].as_str().unwrap_or("<unknown>");
+        log_info!(
+            "Bot token is valid: @{} (ID {}), {} ms",
+            username,
+            id,
+            elapsed
+        );
+        Ok(())
+    }
+
+    pub fn export_invite_link(&self) -> Result<()> {
+        let bot = self.get_me()?;
+        let bot_id = bot["id"]
+            .as_i64()
+            .ok_or_else(|| anyhow!("getMe response did not include the bot ID"))?;
+
+        let member_url = method_url(&self.api_url, &self.bot_token, "getChatMember");
+        let response = self
+            .client
+            .get(&member_url)
+            .query(&[
+                ("chat_id", self.chat_id.clone()),
+                ("user_id", bot_id.to_string()),
+            ])
+            .send();
+        let body = self.handle_response("Failed to check bot permissions:", response)?;
+        let member: Value = serde_json::from_str(&body)
+            .map_err(|err| anyhow!("Failed to parse getChatMember response: {}", err))?;
+        let member = &member["result"];
+        let can_invite = match member["status"].as_str() {
+            Some("creator") => true,
+            Some("administrator") => member["can_invite_users"].as_bool().unwrap_or(false),
+            _ => false,
+        };
+        if !can_invite {
+            return Err(anyhow!(
+                "The bot must be an administrator with the 'Invite users' right in {} to export an invite link",
+                self.chat_id
+            ));
+        }
+
+        let url = method_url(&self.api_url, &self.bot_token, "exportChatInviteLink");
+        let response = self
+            .client
+            .post(&url)
+            .json(&json!({ "chat_id": self.chat_id }))
+            .send();
+        let body = self.handle_response("Failed to export invite link:", response)?;
+        let value: Value = serde_json::from_str(&body)
+            .map_err(|err| anyhow!("Failed to parse exportChatInviteLink response: {}", err))?;
+        let link = value["result"]
+            .as_str()
+            .ok_or_else(|| anyhow!("exportChatInviteLink did not return a link"))?;
+        println!("{}", link);
+        Ok(())
+    }
+
     fn check(&mut self, chat_id: &str, thread_id: Option<i64>) -> Result<()> {
+        if !self.dry_run {
+            self.check_token()?;
+        }
+
         let actions = [
             "typing",
             "upload_photo",
@@ -695,14 +2144,29 @@ impl SendTg {
             payload["message_thread_id"] = json!(id);
         }
 
-        let url = format!("{}{}/sendChatAction", self.api_url, self.bot_token);
+        if self.dry_run {
+            self.log_dry_run("sendChatAction", action, 0);
+            return Ok(());
+        }
+
+        let url = method_url(&self.api_url, &self.bot_token, "sendChatAction");
         let start = Instant::now();
         let response = self.client.post(&url).json(&payload).send();
 
         match self.handle_response("Failed to send chat action:", response) {
             Ok(_) => {
                 let elapsed = start.elapsed().as_millis();
-                log_info!("{} API Response time: {} ms", self.api_url, elapsed);
+                let format = crate::output::format();
+                if format.is_text() {
+                    log_info!("{} API Response time: {} ms", self.api_url, elapsed);
+                } else {
+                    format.print_record(&[
+                        ("api_url", json!(self.api_url)),
+                        ("chat_id", json!(chat_id)),
+                        ("action", json!(action)),
+                        ("response_ms", json!(elapsed)),
+                    ]);
+                }
                 Ok(())
             }
             Err(err) => Err(err),
@@ -717,9 +2181,9 @@ impl SendTg {
         match response {
             Ok(resp) => self.ensure_success(context, resp),
             Err(err) => {
-                let error = anyhow!(err);
-                self.log_exception(context, &error, None, None);
-                Err(error)
+                self.log_exception(context, &anyhow!(err.to_string()), None, None);
+                // The request URL carries the bot token; keep it out of the returned error.
+                Err(anyhow!(err.without_url()))
             }
         }
     }
@@ -749,10 +2213,10 @@ impl SendTg {
     ) {
         let sanitized = error.to_string().replace(&self.bot_token, "REDACTED");
         log_error!("{} {}", context, sanitized);
-        if let Some(status) = status {
-            if let Some(body) = response {
-                log_debug!("HTTP Status Code: {}, Response: {}", status.as_u16(), body);
-            }
+        if let Some(status) = status
+            && let Some(body) = response
+        {
+            log_debug!("HTTP Status Code: {}, Response: {}", status.as_u16(), body);
         }
     }
 
@@ -780,18 +2244,18 @@ impl SendTg {
                         return Ok(text);
                     }
 
-                    if attempt < max_retries {
-                        if let Some(wait) = Self::retry_after_secs(status, &text) {
-                            attempt += 1;
-                            log_info!(
-                                "Rate limited: retrying in {} s (attempt {} of {})",
-                                wait,
-                                attempt,
-                                max_retries
-                            );
-                            std::thread::sleep(Duration::from_secs(wait));
-                            continue;
-                        }
+                    if attempt < max_retries
+                        && let Some(wait) = Self::retry_after_secs(status, &text)
+                    {
+                        attempt += 1;
+                        log_info!(
+                            "Rate limited: retrying in {} s (attempt {} of {})",
+                            wait,
+                            attempt,
+                            max_retries
+                        );
+                        std::thread::sleep(Duration::from_secs(wait));
+                        continue;
                     }
 
                     let err = anyhow!("telegram API returned status {}", status);
@@ -799,9 +2263,8 @@ impl SendTg {
                     return Err(err);
                 }
                 Err(err) => {
-                    let error = anyhow!(err);
-                    self.log_exception(context, &error, None, None);
-                    return Err(error);
+                    self.log_exception(context, &anyhow!(err.to_string()), None, None);
+                    return Err(anyhow!(err.without_url()));
                 }
             }
         }
@@ -815,24 +2278,23 @@ impl SendTg {
             return Some(1);
         }
 
-        if body.to_ascii_lowercase().contains("too many requests") {
-            if let Some(val) = Self::retry_after_from_body(body) {
-                return Some(val);
-            }
+        if body.to_ascii_lowercase().contains("too many requests")
+            && let Some(val) = Self::retry_after_from_body(body)
+        {
+            return Some(val);
         }
 
         None
     }
 
     fn retry_after_from_body(body: &str) -> Option<u64> {
-        if let Ok(value) = serde_json::from_str::<Value>(body) {
-            if let Some(val) = value
+        if let Ok(value) = serde_json::from_str::<Value>(body)
+            && let Some(val) = value
                 .get("parameters")
                 .and_then(|p| p.get("retry_after"))
                 .and_then(|v| v.as_u64())
-            {
-                return Some(val);
-            }
+        {
+            return Some(val);
         }
 
         let needle = "retry after";
@@ -852,6 +2314,71 @@ impl SendTg {
     }
 }
 
+/// Failed sends, labelled with the file they were for. With
+/// `--continue-on-error` they are collected and reported together.
+struct SendFailures {
+    continue_on_error: bool,
+    errors: Vec<anyhow::Error>,
+}
+
+impl SendFailures {
+    fn new(continue_on_error: bool) -> Self {
+        Self {
+            continue_on_error,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Returns the labelled error when sending should stop.
+    fn record(&mut self, label: &str, result: Result<()>) -> Result<()> {
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) if self.continue_on_error => {
+                log_info!("Continuing with remaining media after a failed send");
+                self.collect(label, Err(err));
+                Ok(())
+            }
+            Err(err) => Err(err.context(label.to_string())),
+        }
+    }
+
+    fn collect(&mut self, label: &str, result: Result<()>) {
+        if let Err(err) = result {
+            self.errors.push(err.context(label.to_string()));
+        }
+    }
+
+    fn finish(self, attempted: usize, what: &str) -> Result<()> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        let details: Vec<String> = self.errors.iter().map(|err| format!("{:#}", err)).collect();
+        Err(anyhow!(
+            "{} of {} {} failed: {}",
+            self.errors.len(),
+            attempted,
+            what,
+            details.join("; ")
+        ))
+    }
+}
+
+fn group_label(items: &[MediaItem]) -> String {
+    let names: Vec<&str> = items.iter().map(|item| item.file_name.as_str()).collect();
+    format!("album ({})", names.join(", "))
+}
+
+fn pause_before_request(calls: usize, delay_secs: Option<u64>) {
+    let delay = delay_secs.unwrap_or(0);
+    if calls > 0 && delay > 0 {
+        log_debug!(
+            "Waiting {} s before next media request to reduce rate limiting",
+            delay
+        );
+        std::thread::sleep(Duration::from_secs(delay));
+    }
+}
+
 #[derive(Serialize)]
 struct InputMedia {
     #[serde(rename = "type")]
@@ -860,6 +2387,8 @@ struct InputMedia {
     #[serde(skip_serializing_if = "Option::is_none")]
     caption: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    parse_mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     has_spoiler: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     width: Option<u32>,
@@ -871,6 +2400,12 @@ struct InputMedia {
     thumbnail: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     supports_streaming: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disable_content_type_detection: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    performer: Option<String>,
 }
 
 #[derive(serde::Deserialize)]
@@ -887,15 +2422,133 @@ struct ChatResult {
     first_name: Option<String>,
     #[serde(rename = "last_name")]
     last_name: Option<String>,
+    username: Option<String>,
 }
 
 #[derive(Clone)]
-struct MediaItem {
+pub struct MediaItem {
     media_type: String,
     file_name: String,
     path: PathBuf,
     caption: Option<String>,
     spoiler: bool,
+    size: u64,
     metadata: Option<utils::MediaMetadata>,
     part_name: String,
 }
+
+impl MediaItem {
+    fn payload_size(&self) -> u64 {
+        let thumb_bytes = self
+            .metadata
+            .as_ref()
+            .and_then(utils::MediaMetadata::thumbnail)
+            .map(|t| t.len() as u64)
+            .unwrap_or(0);
+        self.size + thumb_bytes
+    }
+}
+
+/// Non-blocking client for embedding in async services. It covers plain
+/// messages and single media files; the CLI features (progress bars, media
+/// groups, metadata extraction) stay with [`SendTg`].
+#[cfg(feature = "async")]
+pub struct AsyncSendTg {
+    api_url: String,
+    bot_token: String,
+    pub chat_id: String,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "async")]
+impl AsyncSendTg {
+    pub fn new(api_url: String, bot_token: String, chat_id: String) -> Result<Self> {
+        if bot_token.trim().is_empty() {
+            return Err(anyhow!("Bot token is missing!"));
+        }
+        if api_url.trim().is_empty() {
+            return Err(anyhow!("API URL is missing!"));
+        }
+        let chat_id = chat_id.trim().to_string();
+        if !utils::is_valid_chat_id(&chat_id) {
+            return Err(anyhow!(
+                "Chat ID must be a numeric ID (e.g. -1001234567890) or an @username"
+            ));
+        }
+
+        Ok(Self {
+            api_url,
+            bot_token,
+            chat_id,
+            client: reqwest::Client::builder()
+                .danger_accept_invalid_certs(accept_invalid_certs())
+                .build()?,
+        })
+    }
+
+    /// Sends an HTML message and returns the API response body.
+    pub async fn send_message(&self, message: &str, silent: bool) -> Result<Value> {
+        let payload = json!({
+            "chat_id": self.chat_id,
+            "text": message,
+            "parse_mode": "HTML",
+            "disable_notification": silent,
+        });
+        let url = method_url(&self.api_url, &self.bot_token, "sendMessage");
+        let response = self.client.post(&url).json(&payload).send().await;
+        self.handle_response("Failed to send message:", response)
+            .await
+    }
+
+    /// Uploads one file as a photo, video, audio, animation or document,
+    /// depending on its content type.
+    pub async fn send_media(&self, path: &Path, caption: Option<&str>) -> Result<Value> {
+        let media_type = utils::determine_media_type(utils::detect_mime_type(path).as_deref());
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "file".to_string());
+        let bytes = tokio::fs::read(path)
+            .await
+            .map_err(|err| anyhow!("Failed to read {}: {}", path.display(), err))?;
+
+        let mut form = reqwest::multipart::Form::new()
+            .text("chat_id", self.chat_id.clone())
+            .part(
+                media_type.to_string(),
+                reqwest::multipart::Part::bytes(bytes).file_name(file_name),
+            );
+        if let Some(caption) = caption {
+            form = form
+                .text("caption", caption.to_string())
+                .text("parse_mode", "HTML");
+        }
+
+        let method = format!("send{}", utils::capitalize(media_type));
+        let url = method_url(&self.api_url, &self.bot_token, &method);
+        let response = self.client.post(&url).multipart(form).send().await;
+        self.handle_response("Failed to send media:", response)
+            .await
+    }
+
+    async fn handle_response(
+        &self,
+        context: &str,
+        response: reqwest::Result<reqwest::Response>,
+    ) -> Result<Value> {
+        let redact = |text: String| text.replace(&self.bot_token, "REDACTED");
+        let response =
+            response.map_err(|err| anyhow!("{} {}", context, redact(err.to_string())))?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            log_debug!("HTTP Status Code: {}, Response: {}", status.as_u16(), text);
+            return Err(anyhow!(
+                "{} telegram API returned status {}",
+                context,
+                status
+            ));
+        }
+        Ok(serde_json::from_str(&text)?)
+    }
+}
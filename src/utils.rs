@@ -1,23 +1,54 @@
-use crate::args::ButtonSpec;
-use crate::{log_debug, log_info};
+use crate::args::{ButtonSpec, ReplyKeyboard};
+use crate::{log_debug, log_error, log_info, log_warn};
 use anyhow::{Context, anyhow};
-use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use mime_guess::MimeGuess;
 use rand::Rng;
 use serde_json::{Value, json};
 use std::fs::File;
-use std::io::{self, ErrorKind, Read};
+use std::io::{self, ErrorKind, IsTerminal, Read, Write};
 use std::path::Path;
-use std::process::Command;
-use std::time::Duration;
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempPath;
 
-pub(crate) fn redact_token(token: &str) -> String {
+pub fn redact_token(token: &str) -> String {
     if token.len() <= 10 {
         return "REDACTED".to_string();
     }
     format!("{}{}", &token[..10], "*".repeat(30))
 }
 
+/// Bot tokens look like `123456789:AAE...`: a numeric bot ID, a colon and a secret.
+pub fn is_valid_token_format(token: &str) -> bool {
+    let Some((bot_id, secret)) = token.split_once(':') else {
+        return false;
+    };
+    !bot_id.is_empty()
+        && bot_id.chars().all(|c| c.is_ascii_digit())
+        && !secret.is_empty()
+        && secret
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Numeric chat IDs (`-100…` for channels) or public `@username`s, which the
+/// Bot API resolves itself.
+pub fn is_valid_chat_id(chat_id: &str) -> bool {
+    match chat_id.strip_prefix('@') {
+        Some(username) => {
+            (4..=32).contains(&username.len())
+                && username
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => chat_id.parse::<i64>().is_ok(),
+    }
+}
+
 pub(crate) fn detect_mime_type(path: &Path) -> Option<String> {
     let guess = MimeGuess::from_path(path).first_raw();
     if guess.is_some() {
@@ -35,8 +66,343 @@ pub(crate) fn detect_mime_type(path: &Path) -> Option<String> {
     )
 }
 
+/// Copy media piped on stdin to a temporary file so uploads can be retried.
+/// The file name is `stdin` with an extension inferred from the content.
+pub(crate) fn spool_stdin_media() -> anyhow::Result<(TempPath, String)> {
+    let mut stdin = io::stdin().lock();
+    if stdin.is_terminal() {
+        return Err(anyhow!("--media - expects file data piped on stdin"));
+    }
+
+    let mut header = Vec::with_capacity(512);
+    (&mut stdin)
+        .take(512)
+        .read_to_end(&mut header)
+        .context("Failed to read media from stdin")?;
+    if header.is_empty() {
+        return Err(anyhow!("No media data received on stdin"));
+    }
+
+    let extension = infer::Infer::new()
+        .get(&header)
+        .map(|kind| kind.extension());
+    let suffix = extension.map(|ext| format!(".{}", ext)).unwrap_or_default();
+    let mut temp = tempfile::Builder::new()
+        .prefix("sendtg-stdin-")
+        .suffix(&suffix)
+        .tempfile()
+        .context("Failed to create temporary file for stdin media")?;
+    temp.write_all(&header)
+        .and_then(|_| io::copy(&mut stdin, &mut temp).map(|_| ()))
+        .context("Failed to buffer media from stdin")?;
+
+    let file_name = match extension {
+        Some(ext) => format!("stdin.{}", ext),
+        None => "stdin".to_string(),
+    };
+    Ok((temp.into_temp_path(), file_name))
+}
+
+/// Split `text` into parts of at most `limit` visible characters, preferring
+/// paragraph breaks, then line breaks, and cutting mid-line only when a line is
+/// too long. Cuts never fall inside an HTML tag or entity, and tags left open at
+/// a cut are closed there and reopened at the start of the next part.
+pub(crate) fn split_message(text: &str, limit: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    let mut push_piece = |piece: &str, separator: &str, current: &mut String| {
+        let needed = if current.is_empty() {
+            visible_len(piece)
+        } else {
+            visible_len(current) + separator.chars().count() + visible_len(piece)
+        };
+        if needed <= limit {
+            if !current.is_empty() {
+                current.push_str(separator);
+            }
+            current.push_str(piece);
+        } else {
+            if !current.is_empty() {
+                chunks.push(std::mem::take(current));
+            }
+            current.push_str(piece);
+        }
+    };
+
+    for paragraph in text.split("\n\n") {
+        if visible_len(paragraph) <= limit {
+            push_piece(paragraph, "\n\n", &mut current);
+            continue;
+        }
+        for (index, line) in paragraph.split('\n').enumerate() {
+            let separator = if index == 0 { "\n\n" } else { "\n" };
+            if visible_len(line) <= limit {
+                push_piece(line, separator, &mut current);
+                continue;
+            }
+            for (part_index, part) in cut_line(line, limit).iter().enumerate() {
+                let separator = if part_index == 0 { separator } else { "" };
+                push_piece(part, separator, &mut current);
+            }
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    let mut chunks = close_open_tags(chunks);
+    chunks.retain(|chunk| {
+        html_atoms(chunk)
+            .iter()
+            .any(|atom| !is_tag(atom) && !atom.trim().is_empty())
+    });
+    if chunks.is_empty() {
+        chunks.push(text.to_string());
+    }
+    chunks
+}
+
+/// Cuts a single line into parts of `limit` visible characters. Opening tags
+/// directly before a cut move to the next part so no part ends in an empty tag.
+fn cut_line(line: &str, limit: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut pending_tags = String::new();
+    let mut width = 0;
+    for atom in html_atoms(line) {
+        if is_tag(atom) {
+            if atom.starts_with("</") {
+                current.push_str(&std::mem::take(&mut pending_tags));
+                current.push_str(atom);
+            } else {
+                pending_tags.push_str(atom);
+            }
+            continue;
+        }
+        if width == limit {
+            parts.push(std::mem::take(&mut current));
+            width = 0;
+        }
+        current.push_str(&std::mem::take(&mut pending_tags));
+        current.push_str(atom);
+        width += 1;
+    }
+    current.push_str(&pending_tags);
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Closes the tags each chunk leaves open and reopens them in the next one.
+fn close_open_tags(chunks: Vec<String>) -> Vec<String> {
+    let mut open: Vec<String> = Vec::new();
+    let mut balanced = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        let mut text = open.concat();
+        text.push_str(&chunk);
+        for tag in html_atoms(&chunk).into_iter().filter(|atom| is_tag(atom)) {
+            let name = tag_name(tag);
+            if tag.starts_with("</") {
+                if let Some(position) = open.iter().rposition(|open| tag_name(open) == name) {
+                    open.remove(position);
+                }
+            } else if !tag.ends_with("/>") {
+                open.push(tag.to_string());
+            }
+        }
+        for tag in open.iter().rev() {
+            text.push_str(&format!("</{}>", tag_name(tag)));
+        }
+        balanced.push(text);
+    }
+    balanced
+}
+
+/// Splits HTML into tags, `&...;` entities and single characters.
+fn html_atoms(text: &str) -> Vec<&str> {
+    let mut atoms = Vec::new();
+    let mut rest = text;
+    while let Some(first) = rest.chars().next() {
+        let len = match first {
+            '<' => rest
+                .find(['>', '\n'])
+                .filter(|&end| rest[end..].starts_with('>'))
+                .map(|end| end + 1),
+            '&' => rest
+                .find(';')
+                .filter(|&end| {
+                    (2..=10).contains(&end)
+                        && rest[1..end]
+                            .chars()
+                            .all(|ch| ch.is_ascii_alphanumeric() || ch == '#')
+                })
+                .map(|end| end + 1),
+            _ => None,
+        }
+        .unwrap_or(first.len_utf8());
+        atoms.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+    atoms
+}
+
+fn is_tag(atom: &str) -> bool {
+    atom.len() > 2 && atom.starts_with('<') && atom.ends_with('>')
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .split(|ch: char| ch.is_whitespace() || ch == '>' || ch == '/')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// Characters Telegram counts towards the length limit: tags are free and an
+/// entity counts as one.
+pub(crate) fn visible_len(text: &str) -> usize {
+    html_atoms(text).iter().filter(|atom| !is_tag(atom)).count()
+}
+
+fn template_builtin(name: &str) -> Option<String> {
+    match name {
+        "datetime" => Some(chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+        "hostname" => Some(hostname()),
+        "username" => Some(
+            std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .unwrap_or_else(|_| "unknown".to_string()),
+        ),
+        _ => None,
+    }
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| {
+            Command::new("hostname")
+                .output()
+                .ok()
+                .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        })
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Replace `{name}` placeholders with `--var` values or built-ins. Unknown
+/// placeholders are kept as written and reported once.
+pub(crate) fn expand_template(text: &str, vars: &[(String, String)]) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut unknown: Vec<&str> = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name_len = after
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(after.len());
+        let name = &after[..name_len];
+        if name.is_empty() || !after[name_len..].starts_with('}') {
+            output.push('{');
+            rest = after;
+            continue;
+        }
+
+        let value = vars
+            .iter()
+            .rev()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+            .or_else(|| template_builtin(name));
+        match value {
+            Some(value) => output.push_str(&value),
+            None => {
+                if !unknown.contains(&name) {
+                    unknown.push(name);
+                }
+                output.push_str(&rest[start..start + name_len + 2]);
+            }
+        }
+        rest = &after[name_len + 1..];
+    }
+    output.push_str(rest);
+
+    for name in unknown {
+        log_warn!("Unknown template variable {{{}}} left unchanged", name);
+    }
+    output
+}
+
+pub fn shell_command(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+    shell.arg(command);
+    shell
+}
+
+/// POST `payload` as JSON with a short-lived client so a slow endpoint cannot
+/// hold up the exit for long.
+pub fn post_webhook(url: &str, payload: &Value) -> anyhow::Result<()> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let response = client
+        .post(url)
+        .json(payload)
+        .send()
+        .with_context(|| format!("Failed to reach webhook {}", url))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Webhook {} returned {}", url, response.status()));
+    }
+    Ok(())
+}
+
+/// Run `command` through the platform shell and return its output as message text.
+pub(crate) fn run_shell_command(command: &str, include_stderr: bool) -> anyhow::Result<String> {
+    let output = shell_command(command)
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run command '{}'", command))?;
+
+    if !output.status.success() {
+        log_warn!("Command '{}' exited with {}", command, output.status);
+    }
+
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    if include_stderr {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.trim().is_empty() {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str(&stderr);
+        }
+    }
+
+    let text = text.trim_end().to_string();
+    if text.trim().is_empty() {
+        return Err(anyhow!("Command '{}' produced no output to send", command));
+    }
+    Ok(text)
+}
+
 pub(crate) fn determine_media_type(mime_type: Option<&str>) -> &'static str {
     match mime_type {
+        Some("image/gif") => "animation",
         Some(mt) if mt.starts_with("image/") => "photo",
         Some(mt) if mt.starts_with("video/") => "video",
         Some(mt) if mt.starts_with("audio/") => "audio",
@@ -57,6 +423,9 @@ pub(crate) fn create_reply_markup(buttons: &[ButtonSpec]) -> Option<Value> {
             ButtonSpec::Link { text, url } => {
                 current_row.push(json!({ "text": text, "url": url }));
             }
+            ButtonSpec::Callback { text, data } => {
+                current_row.push(json!({ "text": text, "callback_data": data }));
+            }
             ButtonSpec::RowBreak => {
                 if !current_row.is_empty() {
                     rows.push(current_row);
@@ -77,6 +446,39 @@ pub(crate) fn create_reply_markup(buttons: &[ButtonSpec]) -> Option<Value> {
     }
 }
 
+pub(crate) fn create_reply_keyboard(keyboard: &ReplyKeyboard) -> Value {
+    let rows: Vec<Vec<Value>> = keyboard
+        .rows
+        .iter()
+        .map(|row| row.iter().map(|text| json!({ "text": text })).collect())
+        .collect();
+    json!({
+        "keyboard": rows,
+        "one_time_keyboard": keyboard.one_time,
+        "resize_keyboard": keyboard.resize,
+    })
+}
+
+pub(crate) fn extract_message_ids(body: &str) -> Vec<i64> {
+    let value: Value = match serde_json::from_str(body) {
+        Ok(value) => value,
+        Err(_) => return Vec::new(),
+    };
+
+    match value.get("result") {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| item.get("message_id").and_then(Value::as_i64))
+            .collect(),
+        Some(result) => result
+            .get("message_id")
+            .and_then(Value::as_i64)
+            .into_iter()
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
 pub(crate) fn validate_defaults(
     provided_api_url: bool,
     provided_bot_token: bool,
@@ -101,6 +503,74 @@ pub(crate) fn is_regular_file(path: &Path) -> bool {
     path.is_file()
 }
 
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if value < 1000.0 {
+            break;
+        }
+        value /= 1000.0;
+        unit = next;
+    }
+    format!("{:.1} {}", value, unit)
+}
+
+/// Report every unusable media path at once so nothing is sent until all are fixed.
+pub(crate) fn check_media_files(paths: &[std::path::PathBuf]) -> anyhow::Result<()> {
+    let mut failed = 0;
+    for path in paths.iter().filter(|path| path.as_os_str() != "-") {
+        let problem = if !path.exists() {
+            Some("file not found".to_string())
+        } else if !is_regular_file(path) {
+            Some("not a regular file".to_string())
+        } else {
+            match std::fs::metadata(path).and_then(|metadata| {
+                File::open(path)?;
+                Ok(metadata.len())
+            }) {
+                Ok(0) => Some("file is empty".to_string()),
+                Ok(_) => None,
+                Err(err) => Some(format!("cannot be read: {}", err)),
+            }
+        };
+
+        match problem {
+            Some(problem) => {
+                log_error!("{}: {}", path.display(), problem);
+                failed += 1;
+            }
+            None => {
+                log_debug!(
+                    "{}: ok ({})",
+                    path.display(),
+                    detect_mime_type(path).as_deref().unwrap_or("unknown type")
+                );
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow!(
+            "{} of {} media files failed the check; nothing was sent",
+            failed,
+            paths.len()
+        ));
+    }
+    log_info!("All {} media files passed the check", paths.len());
+    Ok(())
+}
+
 pub(crate) fn capitalize(input: &str) -> String {
     let mut chars = input.chars();
     match chars.next() {
@@ -109,22 +579,117 @@ pub(crate) fn capitalize(input: &str) -> String {
     }
 }
 
+static PROGRESS_STDOUT: AtomicBool = AtomicBool::new(false);
+static NO_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn set_progress_stdout(stdout: bool) {
+    PROGRESS_STDOUT.store(stdout, Ordering::Relaxed);
+}
+
+pub(crate) fn set_no_progress(disabled: bool) {
+    NO_PROGRESS.store(disabled, Ordering::Relaxed);
+}
+
+fn progress_draw_target() -> ProgressDrawTarget {
+    if PROGRESS_STDOUT.load(Ordering::Relaxed) {
+        ProgressDrawTarget::stdout()
+    } else {
+        ProgressDrawTarget::stderr()
+    }
+}
+
+const PROGRESS_TEMPLATE: &str = "{spinner:.green} {msg:<25} [{bar:25.cyan/blue}] {decimal_bytes}/{decimal_total_bytes} {decimal_bytes_per_sec} ({eta}) {percent}%";
+
+fn progress_style() -> ProgressStyle {
+    ProgressStyle::with_template(PROGRESS_TEMPLATE)
+        .unwrap()
+        .progress_chars("#>-")
+        .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈")
+}
+
+/// Shared progress display for a media group: one bar for the total bytes
+/// of every file, with a sub-bar for the file currently being uploaded.
+#[derive(Clone)]
+pub struct GroupProgress {
+    multi: MultiProgress,
+    total: ProgressBar,
+    label: String,
+}
+
+impl GroupProgress {
+    pub fn new(paths: &[&Path]) -> Self {
+        let total_bytes = paths
+            .iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+        let label = format!("{} files", paths.len());
+
+        let multi = if NO_PROGRESS.load(Ordering::Relaxed) {
+            MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+        } else {
+            MultiProgress::with_draw_target(progress_draw_target())
+        };
+        let total = multi.add(ProgressBar::new(total_bytes));
+        total.set_style(progress_style());
+        total.set_message(truncate_label(&format!("Total ({})", label), 24));
+        if !NO_PROGRESS.load(Ordering::Relaxed) {
+            total.enable_steady_tick(Duration::from_millis(100));
+        }
+
+        Self {
+            multi,
+            total,
+            label,
+        }
+    }
+
+    fn advance(&self, amount: u64) {
+        self.total.inc(amount);
+    }
+
+    /// Clears the bar even when some files never streamed (cache hits or failures).
+    pub fn finish(&self) {
+        if !self.total.is_finished() {
+            self.total.finish_and_clear();
+        }
+    }
+
+    fn finish_if_complete(&self) {
+        if self.total.is_finished() {
+            return;
+        }
+        if self.total.position() >= self.total.length().unwrap_or(0) {
+            self.total.finish_and_clear();
+            log_info!("Waiting for Telegram to process {}", self.label);
+        }
+    }
+}
+
 pub struct ProgressReader<R> {
     inner: R,
     progress: ProgressBar,
     label: String,
     started: bool,
     finished: bool,
+    group: Option<GroupProgress>,
 }
 
 impl<R> ProgressReader<R> {
-    fn new(inner: R, progress: ProgressBar, label: String, started: bool, finished: bool) -> Self {
+    fn new(
+        inner: R,
+        progress: ProgressBar,
+        label: String,
+        finished: bool,
+        group: Option<GroupProgress>,
+    ) -> Self {
         Self {
             inner,
             progress,
             label,
-            started,
+            started: false,
             finished,
+            group,
         }
     }
 
@@ -133,33 +698,47 @@ impl<R> ProgressReader<R> {
             return;
         }
         self.started = true;
-        self.progress.set_draw_target(ProgressDrawTarget::stdout());
-        self.progress.set_style(
-            ProgressStyle::with_template(
-                "{spinner:.green} {msg:<25} [{bar:25.cyan/blue}] {decimal_bytes}/{decimal_total_bytes} {decimal_bytes_per_sec} ({eta}) {percent}%",
-            )
-            .unwrap()
-            .progress_chars("#>-")
-            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈"),
-        );
+        if NO_PROGRESS.load(Ordering::Relaxed) {
+            return;
+        }
+        match self.group.as_ref() {
+            Some(group) => {
+                self.progress = group.multi.add(self.progress.clone());
+            }
+            None => self.progress.set_draw_target(progress_draw_target()),
+        }
+        self.progress.set_style(progress_style());
         self.progress.set_message(self.label.clone());
         self.progress.enable_steady_tick(Duration::from_millis(100));
     }
+
+    fn finish(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.start_if_needed();
+        self.progress.finish_and_clear();
+        match self.group.as_ref() {
+            Some(group) => group.finish_if_complete(),
+            None => {
+                log_info!("Waiting for Telegram to process {}", self.label.clone());
+            }
+        }
+        self.finished = true;
+    }
 }
 
 impl<R: Read> Read for ProgressReader<R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let amount = self.inner.read(buf)?;
         if amount == 0 {
-            if !self.finished {
-                self.start_if_needed();
-                self.progress.finish_and_clear();
-                log_info!("Waiting for Telegram to process {}", self.label.clone());
-                self.finished = true;
-            }
+            self.finish();
         } else {
             self.start_if_needed();
             self.progress.inc(amount as u64);
+            if let Some(group) = self.group.as_ref() {
+                group.advance(amount as u64);
+            }
         }
         Ok(amount)
     }
@@ -167,30 +746,27 @@ impl<R: Read> Read for ProgressReader<R> {
 
 impl<R> Drop for ProgressReader<R> {
     fn drop(&mut self) {
-        if !self.finished {
-            self.start_if_needed();
-            self.progress.finish_and_clear();
-            log_info!("Waiting for Telegram to process {}", self.label.clone());
-            self.finished = true;
-        }
+        self.finish();
     }
 }
 
 fn truncate_label(label: &str, max_chars: usize) -> String {
     let mut result = String::new();
-    let mut count = 0;
-    for ch in label.chars() {
+    for (count, ch) in label.chars().enumerate() {
         if count + 1 > max_chars {
             result.push('…');
             return result;
         }
         result.push(ch);
-        count += 1;
     }
     result
 }
 
-pub fn progress_reader_for_path(path: &Path, label: &str) -> anyhow::Result<ProgressReader<File>> {
+pub fn progress_reader_for_path(
+    path: &Path,
+    label: &str,
+    group: Option<&GroupProgress>,
+) -> anyhow::Result<ProgressReader<File>> {
     let file = File::open(path)
         .with_context(|| format!("Failed to open media {} for upload", path.display()))?;
     let metadata = file
@@ -202,17 +778,47 @@ pub fn progress_reader_for_path(path: &Path, label: &str) -> anyhow::Result<Prog
     let progress = ProgressBar::new(total_bytes);
     progress.set_draw_target(ProgressDrawTarget::hidden());
 
-    let (started, finished) = if total_bytes == 0 {
-        (false, true)
-    } else {
-        (false, false)
-    };
-
     Ok(ProgressReader::new(
-        file, progress, truncated, started, finished,
+        file,
+        progress,
+        truncated,
+        total_bytes == 0,
+        group.cloned(),
     ))
 }
 
+pub const DEFAULT_THUMBNAIL_SIZE: (u32, u32) = (320, 320);
+pub const DEFAULT_METADATA_TIMEOUT_SECS: u64 = 30;
+pub const DEFAULT_UPLOAD_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct MetadataOptions {
+    pub thumbnail_at: Option<f64>,
+    pub thumbnail_size: (u32, u32),
+    pub ffmpeg: String,
+    pub ffprobe: String,
+    pub timeout: Duration,
+}
+
+impl Default for MetadataOptions {
+    fn default() -> Self {
+        Self {
+            thumbnail_at: None,
+            thumbnail_size: DEFAULT_THUMBNAIL_SIZE,
+            ffmpeg: "ffmpeg".to_string(),
+            ffprobe: "ffprobe".to_string(),
+            timeout: Duration::from_secs(DEFAULT_METADATA_TIMEOUT_SECS),
+        }
+    }
+}
+
+impl MetadataOptions {
+    fn scale_filter(&self) -> String {
+        let (width, height) = self.thumbnail_size;
+        format!("scale={width}:{height}:force_original_aspect_ratio=decrease")
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct VideoMetadata {
     pub duration: Option<u64>,
@@ -224,10 +830,35 @@ pub struct VideoMetadata {
 #[derive(Debug, Clone)]
 pub enum MediaMetadata {
     Video(VideoMetadata),
-    Photo { thumbnail: Option<Vec<u8>> },
+    Photo {
+        thumbnail: Option<Vec<u8>>,
+    },
+    Pdf {
+        thumbnail: Option<Vec<u8>>,
+    },
+    Audio {
+        duration: Option<u64>,
+        title: Option<String>,
+        performer: Option<String>,
+        thumbnail: Option<Vec<u8>>,
+    },
 }
 
-pub fn extract_video_metadata(path: &Path) -> anyhow::Result<Option<VideoMetadata>> {
+impl MediaMetadata {
+    pub fn thumbnail(&self) -> Option<&Vec<u8>> {
+        match self {
+            MediaMetadata::Video(video_meta) => video_meta.thumbnail.as_ref(),
+            MediaMetadata::Photo { thumbnail }
+            | MediaMetadata::Pdf { thumbnail }
+            | MediaMetadata::Audio { thumbnail, .. } => thumbnail.as_ref(),
+        }
+    }
+}
+
+pub fn extract_video_metadata(
+    path: &Path,
+    options: &MetadataOptions,
+) -> anyhow::Result<Option<VideoMetadata>> {
     let path_str = match path.to_str() {
         Some(s) => s,
         None => {
@@ -239,27 +870,28 @@ pub fn extract_video_metadata(path: &Path) -> anyhow::Result<Option<VideoMetadat
         }
     };
 
-    let ffprobe_output = match Command::new("ffprobe")
-        .arg("-v")
-        .arg("error")
-        .arg("-select_streams")
-        .arg("v:0")
-        .arg("-show_entries")
-        .arg("stream=width,height,duration")
-        .arg("-show_entries")
-        .arg("format=duration")
-        .arg("-of")
-        .arg("json")
-        .arg(path_str)
-        .output()
-    {
+    let ffprobe_output = match run_with_timeout(
+        Command::new(&options.ffprobe)
+            .arg("-v")
+            .arg("error")
+            .arg("-select_streams")
+            .arg("v:0")
+            .arg("-show_entries")
+            .arg("stream=width,height,duration")
+            .arg("-show_entries")
+            .arg("format=duration")
+            .arg("-of")
+            .arg("json")
+            .arg(path_str),
+        options.timeout,
+    ) {
         Ok(output) => output,
         Err(err) => {
             if err.kind() == ErrorKind::NotFound {
                 log_debug!("ffprobe not found; skipping video metadata extraction.");
                 return Ok(None);
             }
-            return Err(anyhow!(err).context("Failed to spawn ffprobe process"));
+            return Err(anyhow!(err).context("Failed to run ffprobe"));
         }
     };
 
@@ -304,10 +936,10 @@ pub fn extract_video_metadata(path: &Path) -> anyhow::Result<Option<VideoMetadat
     let mut duration_secs = parse_duration(stream.get("duration"))
         .or_else(|| parse_duration(value.get("format").and_then(|f| f.get("duration"))));
 
-    if let Some(d) = duration_secs.as_mut() {
-        if !d.is_finite() || *d < 0.0 {
-            *d = 0.0;
-        }
+    if let Some(d) = duration_secs.as_mut()
+        && (!d.is_finite() || *d < 0.0)
+    {
+        *d = 0.0;
     }
 
     let width = stream
@@ -321,13 +953,29 @@ pub fn extract_video_metadata(path: &Path) -> anyhow::Result<Option<VideoMetadat
 
     let duration = duration_secs.map(|d| d.floor() as u64);
 
-    let mut rng = rand::thread_rng();
-    let start_seconds = duration_secs
-        .filter(|d| *d > 0.0)
-        .map(|d| if d <= 1.0 { 0.0 } else { rng.gen_range(0.0..d) });
+    let start_seconds = match options.thumbnail_at {
+        Some(at) => match duration_secs {
+            Some(d) if at > d => {
+                log_warn!(
+                    "Thumbnail timestamp {} s is beyond the end of {} ({:.2} s); using 0.0",
+                    at,
+                    path.display(),
+                    d
+                );
+                Some(0.0)
+            }
+            _ => Some(at),
+        },
+        None => {
+            let mut rng = rand::thread_rng();
+            duration_secs
+                .filter(|d| *d > 0.0)
+                .map(|d| if d <= 1.0 { 0.0 } else { rng.gen_range(0.0..d) })
+        }
+    };
 
     let thumbnail = match start_seconds {
-        Some(position) => match generate_thumbnail(path_str, position) {
+        Some(position) => match generate_thumbnail(path_str, position, options) {
             Ok(bytes) => bytes,
             Err(err) => {
                 log_debug!(
@@ -338,7 +986,7 @@ pub fn extract_video_metadata(path: &Path) -> anyhow::Result<Option<VideoMetadat
                 None
             }
         },
-        None => match generate_thumbnail(path_str, 0.0) {
+        None => match generate_thumbnail(path_str, 0.0, options) {
             Ok(bytes) => bytes,
             Err(err) => {
                 log_debug!(
@@ -359,7 +1007,10 @@ pub fn extract_video_metadata(path: &Path) -> anyhow::Result<Option<VideoMetadat
     }))
 }
 
-pub fn extract_photo_metadata(path: &Path) -> anyhow::Result<Option<Option<Vec<u8>>>> {
+pub fn extract_photo_metadata(
+    path: &Path,
+    options: &MetadataOptions,
+) -> anyhow::Result<Option<Option<Vec<u8>>>> {
     let path_str = match path.to_str() {
         Some(s) => s,
         None => {
@@ -371,27 +1022,28 @@ pub fn extract_photo_metadata(path: &Path) -> anyhow::Result<Option<Option<Vec<u
         }
     };
 
-    let output = match Command::new("ffmpeg")
-        .arg("-v")
-        .arg("error")
-        .arg("-i")
-        .arg(path_str)
-        .arg("-frames:v")
-        .arg("1")
-        .arg("-vf")
-        .arg("scale=320:320:force_original_aspect_ratio=decrease")
-        .arg("-f")
-        .arg("mjpeg")
-        .arg("pipe:1")
-        .output()
-    {
+    let output = match run_with_timeout(
+        Command::new(&options.ffmpeg)
+            .arg("-v")
+            .arg("error")
+            .arg("-i")
+            .arg(path_str)
+            .arg("-frames:v")
+            .arg("1")
+            .arg("-vf")
+            .arg(options.scale_filter())
+            .arg("-f")
+            .arg("mjpeg")
+            .arg("pipe:1"),
+        options.timeout,
+    ) {
         Ok(output) => output,
         Err(err) => {
             if err.kind() == ErrorKind::NotFound {
                 log_debug!("ffmpeg not found; skipping photo thumbnail generation.");
                 return Ok(Some(None));
             }
-            return Err(anyhow!(err).context("Failed to spawn ffmpeg process for photo"));
+            return Err(anyhow!(err).context("Failed to run ffmpeg for photo thumbnail"));
         }
     };
 
@@ -416,30 +1068,344 @@ pub fn extract_photo_metadata(path: &Path) -> anyhow::Result<Option<Option<Vec<u
     Ok(Some(Some(output.stdout)))
 }
 
-fn generate_thumbnail(path: &str, timestamp: f64) -> anyhow::Result<Option<Vec<u8>>> {
-    let ffmpeg_output = match Command::new("ffmpeg")
-        .arg("-v")
-        .arg("error")
-        .arg("-ss")
-        .arg(format!("{:.2}", timestamp.max(0.0)))
-        .arg("-i")
-        .arg(path)
-        .arg("-frames:v")
-        .arg("1")
-        .arg("-vf")
-        .arg("scale=320:320:force_original_aspect_ratio=decrease")
-        .arg("-f")
-        .arg("mjpeg")
-        .arg("pipe:1")
-        .output()
-    {
+pub fn extract_pdf_thumbnail(
+    path: &Path,
+    options: &MetadataOptions,
+) -> anyhow::Result<Option<MediaMetadata>> {
+    // ffmpeg renders the first page the same way it grabs a frame from an image.
+    Ok(extract_photo_metadata(path, options)?.map(|thumbnail| MediaMetadata::Pdf { thumbnail }))
+}
+
+pub fn extract_audio_metadata(
+    path: &Path,
+    options: &MetadataOptions,
+) -> anyhow::Result<Option<MediaMetadata>> {
+    let output = match run_with_timeout(
+        Command::new(&options.ffprobe)
+            .arg("-v")
+            .arg("error")
+            .arg("-show_entries")
+            .arg("format=duration:format_tags:stream=codec_type,codec_name")
+            .arg("-of")
+            .arg("json")
+            .arg(path),
+        options.timeout,
+    ) {
+        Ok(output) => output,
+        Err(err) => {
+            if err.kind() == ErrorKind::NotFound {
+                log_debug!("ffprobe not found; skipping audio metadata extraction.");
+                return Ok(None);
+            }
+            return Err(anyhow!(err).context("Failed to run ffprobe"));
+        }
+    };
+
+    if !output.status.success() {
+        log_debug!(
+            "ffprobe failed for {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return Ok(None);
+    }
+
+    let value: Value =
+        serde_json::from_slice(&output.stdout).context("Failed to parse ffprobe JSON output")?;
+    let format = value.get("format");
+
+    let duration = format
+        .and_then(|f| f.get("duration"))
+        .and_then(|v| v.as_str())
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|d| d.is_finite() && *d >= 0.0)
+        .map(|d| d.floor() as u64);
+
+    // Tag names differ in case between containers (ID3 vs. Vorbis comments).
+    let tag = |name: &str| -> Option<String> {
+        format
+            .and_then(|f| f.get("tags"))
+            .and_then(|tags| tags.as_object())
+            .and_then(|tags| {
+                tags.iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .and_then(|(_, v)| v.as_str())
+            })
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+
+    // Embedded album art shows up as a single-frame video stream (usually mjpeg or png).
+    let has_cover = value
+        .get("streams")
+        .and_then(|s| s.as_array())
+        .map(|streams| {
+            streams.iter().any(|stream| {
+                stream.get("codec_type").and_then(|v| v.as_str()) == Some("video")
+                    && matches!(
+                        stream.get("codec_name").and_then(|v| v.as_str()),
+                        Some("mjpeg" | "png")
+                    )
+            })
+        })
+        .unwrap_or(false);
+
+    let thumbnail = if has_cover {
+        extract_photo_metadata(path, options)?.flatten()
+    } else {
+        None
+    };
+
+    Ok(Some(MediaMetadata::Audio {
+        duration,
+        title: tag("title"),
+        performer: tag("artist"),
+        thumbnail,
+    }))
+}
+
+pub fn probe_duration(path: &Path, options: &MetadataOptions) -> anyhow::Result<Option<u64>> {
+    let output = match run_with_timeout(
+        Command::new(&options.ffprobe)
+            .arg("-v")
+            .arg("error")
+            .arg("-show_entries")
+            .arg("format=duration")
+            .arg("-of")
+            .arg("json")
+            .arg(path),
+        options.timeout,
+    ) {
+        Ok(output) => output,
+        Err(err) => {
+            if err.kind() == ErrorKind::NotFound {
+                log_debug!("ffprobe not found; skipping duration probe.");
+                return Ok(None);
+            }
+            return Err(anyhow!(err).context("Failed to run ffprobe"));
+        }
+    };
+
+    if !output.status.success() {
+        log_debug!(
+            "ffprobe failed for {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return Ok(None);
+    }
+
+    let value: Value =
+        serde_json::from_slice(&output.stdout).context("Failed to parse ffprobe JSON output")?;
+    let duration = value
+        .get("format")
+        .and_then(|f| f.get("duration"))
+        .and_then(|v| v.as_str())
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|d| d.is_finite() && *d >= 0.0)
+        .map(|d| d.floor() as u64);
+    Ok(duration)
+}
+
+pub fn convert_to_ogg_opus(path: &Path, options: &MetadataOptions) -> anyhow::Result<Vec<u8>> {
+    let output = run_with_timeout(
+        Command::new(&options.ffmpeg)
+            .arg("-v")
+            .arg("error")
+            .arg("-i")
+            .arg(path)
+            .arg("-vn")
+            .arg("-c:a")
+            .arg("libopus")
+            .arg("-f")
+            .arg("ogg")
+            .arg("pipe:1"),
+        options.timeout,
+    )
+    .map_err(|err| {
+        if err.kind() == ErrorKind::NotFound {
+            anyhow!("ffmpeg is required to convert audio to OGG Opus")
+        } else {
+            anyhow!(err).context("Failed to run ffmpeg for voice conversion")
+        }
+    })?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(anyhow!(
+            "ffmpeg failed to convert {} to OGG Opus: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+pub fn convert_heic_to_jpeg(path: &Path, options: &MetadataOptions) -> anyhow::Result<TempPath> {
+    let output_path = tempfile::Builder::new()
+        .prefix("sendtg-")
+        .suffix(".jpg")
+        .tempfile()
+        .context("Failed to create temporary file for HEIC conversion")?
+        .into_temp_path();
+
+    let output = run_with_timeout(
+        Command::new(&options.ffmpeg)
+            .arg("-v")
+            .arg("error")
+            .arg("-y")
+            .arg("-i")
+            .arg(path)
+            .arg("-frames:v")
+            .arg("1")
+            .arg(&output_path),
+        options.timeout,
+    )
+    .map_err(|err| {
+        if err.kind() == ErrorKind::NotFound {
+            anyhow!("ffmpeg is required to convert HEIC/HEIF images to JPEG")
+        } else {
+            anyhow!(err).context("Failed to run ffmpeg for HEIC conversion")
+        }
+    })?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ffmpeg failed to convert {} to JPEG: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(output_path)
+}
+
+pub fn strip_exif(path: &Path, options: &MetadataOptions) -> anyhow::Result<TempPath> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| format!(".{}", ext))
+        .unwrap_or_else(|| ".jpg".to_string());
+    let output_path = tempfile::Builder::new()
+        .prefix("sendtg-")
+        .suffix(&extension)
+        .tempfile()
+        .context("Failed to create temporary file for EXIF stripping")?
+        .into_temp_path();
+
+    let output = run_with_timeout(
+        Command::new(&options.ffmpeg)
+            .arg("-v")
+            .arg("error")
+            .arg("-y")
+            .arg("-i")
+            .arg(path)
+            .arg("-map_metadata")
+            .arg("-1")
+            .arg("-q:v")
+            .arg("2")
+            .arg(&output_path),
+        options.timeout,
+    )
+    .map_err(|err| {
+        if err.kind() == ErrorKind::NotFound {
+            anyhow!("ffmpeg is required to strip EXIF metadata")
+        } else {
+            anyhow!(err).context("Failed to run ffmpeg for EXIF stripping")
+        }
+    })?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ffmpeg failed to strip metadata from {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(output_path)
+}
+
+/// Runs `command` to completion, killing it if it is still running after `timeout`.
+fn run_with_timeout(command: &mut Command, timeout: Duration) -> io::Result<Output> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drain both pipes on their own threads so a chatty child cannot block on a full pipe.
+    let (stdout_tx, stdout_rx) = mpsc::channel();
+    let (stderr_tx, stderr_rx) = mpsc::channel();
+    if let Some(mut stdout) = child.stdout.take() {
+        thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = stdout.read_to_end(&mut buffer);
+            let _ = stdout_tx.send(buffer);
+        });
+    }
+    if let Some(mut stderr) = child.stderr.take() {
+        thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = stderr.read_to_end(&mut buffer);
+            let _ = stderr_tx.send(buffer);
+        });
+    }
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            // `Child::kill` sends SIGKILL on Unix.
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                ErrorKind::TimedOut,
+                format!("process timed out after {} seconds", timeout.as_secs()),
+            ));
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout_rx.recv().unwrap_or_default(),
+        stderr: stderr_rx.recv().unwrap_or_default(),
+    })
+}
+
+fn generate_thumbnail(
+    path: &str,
+    timestamp: f64,
+    options: &MetadataOptions,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let ffmpeg_output = match run_with_timeout(
+        Command::new(&options.ffmpeg)
+            .arg("-v")
+            .arg("error")
+            .arg("-ss")
+            .arg(format!("{:.2}", timestamp.max(0.0)))
+            .arg("-i")
+            .arg(path)
+            .arg("-frames:v")
+            .arg("1")
+            .arg("-vf")
+            .arg(options.scale_filter())
+            .arg("-f")
+            .arg("mjpeg")
+            .arg("pipe:1"),
+        options.timeout,
+    ) {
         Ok(output) => output,
         Err(err) => {
             if err.kind() == ErrorKind::NotFound {
                 log_debug!("ffmpeg not found; skipping thumbnail generation.");
                 return Ok(None);
             }
-            return Err(anyhow!(err).context("Failed to spawn ffmpeg process"));
+            return Err(anyhow!(err).context("Failed to run ffmpeg for thumbnail"));
         }
     };
 
@@ -463,3 +1429,73 @@ fn generate_thumbnail(path: &str, timestamp: f64) -> anyhow::Result<Option<Vec<u
 
     Ok(Some(ffmpeg_output.stdout))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_message_prefers_paragraph_breaks() {
+        let chunks = split_message("aaa\n\nbbb\n\nccc", 8);
+        assert_eq!(chunks, vec!["aaa\n\nbbb", "ccc"]);
+    }
+
+    #[test]
+    fn split_message_falls_back_to_line_breaks() {
+        let chunks = split_message("aaa\nbbb\nccc", 7);
+        assert_eq!(chunks, vec!["aaa\nbbb", "ccc"]);
+    }
+
+    #[test]
+    fn split_message_cuts_long_lines() {
+        let chunks = split_message("abcdefgh", 3);
+        assert_eq!(chunks, vec!["abc", "def", "gh"]);
+    }
+
+    #[test]
+    fn split_message_keeps_short_text_whole() {
+        assert_eq!(split_message("hello", 4096), vec!["hello"]);
+    }
+
+    #[test]
+    fn split_message_ignores_tags_when_measuring() {
+        assert_eq!(split_message("<b>abc</b>", 3), vec!["<b>abc</b>"]);
+    }
+
+    #[test]
+    fn split_message_reopens_tags_across_paragraphs() {
+        let chunks = split_message("<b>aaa\n\nbbb</b>", 5);
+        assert_eq!(chunks, vec!["<b>aaa</b>", "<b>bbb</b>"]);
+    }
+
+    #[test]
+    fn split_message_reopens_code_blocks_across_lines() {
+        let text = "<pre><code class=\"language-rust\">aaaa\nbbbb</code></pre>";
+        let chunks = split_message(text, 5);
+        assert_eq!(
+            chunks,
+            vec![
+                "<pre><code class=\"language-rust\">aaaa</code></pre>",
+                "<pre><code class=\"language-rust\">bbbb</code></pre>",
+            ]
+        );
+    }
+
+    #[test]
+    fn split_message_reopens_tags_mid_line() {
+        let chunks = split_message("x<i>abcdef</i>", 3);
+        assert_eq!(chunks, vec!["x<i>ab</i>", "<i>cde</i>", "<i>f</i>"]);
+    }
+
+    #[test]
+    fn split_message_moves_opening_tags_past_a_cut() {
+        let chunks = split_message("abc<b>def</b>", 3);
+        assert_eq!(chunks, vec!["abc", "<b>def</b>"]);
+    }
+
+    #[test]
+    fn split_message_never_cuts_inside_entities() {
+        let chunks = split_message("a&amp;b&lt;c", 2);
+        assert_eq!(chunks, vec!["a&amp;", "b&lt;", "c"]);
+    }
+}